@@ -0,0 +1,134 @@
+//! Compares the blocking r2d2 pool driven through `web::block`, which the
+//! repositories used before, with the async deadpool pool they use now.
+//!
+//! Needs a reachable Postgres:
+//!
+//! ```sh
+//! BENCH_DATABASE_URL=postgres://... cargo bench --bench pool
+//! ```
+//!
+//! `BENCH_CONCURRENCY` tasks (64) share `BENCH_QUERIES` queries (5000) on a
+//! pool of `BENCH_POOL_SIZE` connections (16), each query keeping the server
+//! busy for `BENCH_QUERY_MS` milliseconds (2). Everything runs on one
+//! single-threaded actix runtime, like a single worker of the server.
+use std::future::Future;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use actix_web::{rt, web};
+use diesel::r2d2::{ConnectionManager, Pool as BlockingPool};
+use diesel::{sql_query, PgConnection};
+use diesel_async::pooled_connection::deadpool::Pool as AsyncPool;
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use futures_util::future::join_all;
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+struct Settings {
+    concurrency: usize,
+    queries: usize,
+    pool_size: usize,
+    sql: String,
+}
+
+/// Runs `query` `settings.queries` times from `settings.concurrency` tasks
+/// and prints throughput and latency percentiles.
+async fn measure<F, Fut>(name: &str, settings: &Settings, query: F)
+where
+    F: Fn() -> Fut + Clone + 'static,
+    Fut: Future<Output = ()> + 'static,
+{
+    // Opens every connection before timing starts.
+    join_all((0..settings.pool_size).map(|_| query())).await;
+
+    // At least one query per task, so fewer queries than tasks still measures.
+    let per_task = (settings.queries / settings.concurrency).max(1);
+    let started = Instant::now();
+    let tasks = (0..settings.concurrency).map(|_| {
+        let query = query.clone();
+        rt::spawn(async move {
+            let mut latencies = Vec::with_capacity(per_task);
+            for _ in 0..per_task {
+                let sent = Instant::now();
+                query().await;
+                latencies.push(sent.elapsed());
+            }
+            latencies
+        })
+    });
+    let mut latencies: Vec<Duration> = join_all(tasks)
+        .await
+        .into_iter()
+        .flat_map(|task| task.expect("benchmark task panicked"))
+        .collect();
+    let elapsed = started.elapsed();
+
+    latencies.sort();
+    let percentile = |p: usize| latencies[(latencies.len() * p / 100).min(latencies.len() - 1)];
+    println!(
+        "{:<20} {:>6} queries in {:>8.2?} {:>9.0}/s   p50 {:>9.2?}   p99 {:>9.2?}",
+        name,
+        latencies.len(),
+        elapsed,
+        latencies.len() as f64 / elapsed.as_secs_f64(),
+        percentile(50),
+        percentile(99),
+    );
+}
+
+fn main() {
+    let Some(url) = std::env::var("BENCH_DATABASE_URL").ok().or_else(|| std::env::var("DATABASE_URL").ok()) else {
+        eprintln!("Set BENCH_DATABASE_URL to run the pool benchmark");
+        return;
+    };
+    let query_ms: f64 = env_or("BENCH_QUERY_MS", 2.0);
+    let settings = Settings {
+        // At least one task, or there is nobody to split the queries between.
+        concurrency: env_or::<usize>("BENCH_CONCURRENCY", 64).max(1),
+        queries: env_or("BENCH_QUERIES", 5_000),
+        pool_size: env_or("BENCH_POOL_SIZE", 16),
+        sql: format!("SELECT pg_sleep({})", query_ms / 1000.0),
+    };
+    println!(
+        "{} tasks, pool of {}, {}ms per query",
+        settings.concurrency, settings.pool_size, query_ms
+    );
+
+    rt::System::new().block_on(async move {
+        let blocking = BlockingPool::builder()
+            .max_size(settings.pool_size as u32)
+            .build(ConnectionManager::<PgConnection>::new(&url))
+            .expect("r2d2 pool");
+        let sql = settings.sql.clone();
+        measure("r2d2 + web::block", &settings, move || {
+            let (pool, sql) = (blocking.clone(), sql.clone());
+            async move {
+                web::block(move || {
+                    let mut conn = pool.get().expect("r2d2 connection");
+                    diesel::RunQueryDsl::execute(sql_query(sql), &mut conn).expect("query");
+                })
+                .await
+                .expect("blocking task");
+            }
+        })
+        .await;
+
+        let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(&url);
+        let pooled = AsyncPool::builder(manager)
+            .max_size(settings.pool_size)
+            .build()
+            .expect("deadpool pool");
+        let sql = settings.sql.clone();
+        measure("deadpool async", &settings, move || {
+            let (pool, sql) = (pooled.clone(), sql.clone());
+            async move {
+                let mut conn = pool.get().await.expect("deadpool connection");
+                sql_query(sql).execute(&mut conn).await.expect("query");
+            }
+        })
+        .await;
+    });
+}
@@ -0,0 +1,13 @@
+DROP TABLE IF EXISTS account_roles;
+DROP TABLE IF EXISTS role_permissions;
+DROP TABLE IF EXISTS permissions;
+DROP TABLE IF EXISTS roles;
+DROP TABLE IF EXISTS two_factor_methods;
+DROP TABLE IF EXISTS password_reset_tokens;
+DROP TABLE IF EXISTS auth_tokens;
+DROP TABLE IF EXISTS password_hashes;
+DROP TABLE IF EXISTS accounts;
+
+DROP TYPE IF EXISTS token_type_enum;
+DROP TYPE IF EXISTS two_factor_method_enum;
+DROP TYPE IF EXISTS account_status_enum;
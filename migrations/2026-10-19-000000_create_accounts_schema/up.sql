@@ -0,0 +1,115 @@
+-- The schema the service started from, before migrations were versioned.
+-- Databases created by hand back then already have all of it, so every
+-- statement is a no-op where the object exists.
+DO $$
+BEGIN
+    CREATE TYPE account_status_enum AS ENUM ('active', 'locked', 'suspended', 'deleted');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$
+BEGIN
+    CREATE TYPE two_factor_method_enum AS ENUM ('none', 'email', 'whatsapp', 'totp', 'sms');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$
+BEGIN
+    CREATE TYPE token_type_enum AS ENUM ('access', 'refresh');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    username VARCHAR(255) NOT NULL UNIQUE,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    is_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    phone_number VARCHAR,
+    status account_status_enum NOT NULL DEFAULT 'active',
+    last_login TIMESTAMPTZ,
+    two_factor_method two_factor_method_enum NOT NULL DEFAULT 'none',
+    registration_date TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    preferred_language VARCHAR NOT NULL DEFAULT 'en',
+    login_attempts INT4 NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS password_hashes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    password_hash BYTEA NOT NULL,
+    salt BYTEA NOT NULL,
+    algorithm VARCHAR(255) NOT NULL,
+    is_temporary BOOLEAN NOT NULL DEFAULT FALSE,
+    expiry TIMESTAMPTZ NOT NULL,
+    last_change_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS auth_tokens (
+    jti UUID PRIMARY KEY,
+    sub UUID NOT NULL REFERENCES accounts (id),
+    expires TIMESTAMPTZ NOT NULL,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ip_address INET NOT NULL,
+    device_info JSON NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    token_type token_type_enum NOT NULL,
+    authorization_type VARCHAR(255) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES accounts (id),
+    token VARCHAR NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS two_factor_methods (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES accounts (id),
+    method two_factor_method_enum NOT NULL,
+    is_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    backup_codes JSON,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS roles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS permissions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_id UUID NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+    permission_id UUID NOT NULL REFERENCES permissions (id) ON DELETE CASCADE,
+    read BOOLEAN NOT NULL DEFAULT FALSE,
+    write BOOLEAN NOT NULL DEFAULT FALSE,
+    update BOOLEAN NOT NULL DEFAULT FALSE,
+    delete BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (role_id, permission_id)
+);
+
+CREATE TABLE IF NOT EXISTS account_roles (
+    account_id UUID NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    role_id UUID NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (account_id, role_id)
+);
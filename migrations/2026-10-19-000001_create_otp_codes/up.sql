@@ -0,0 +1,15 @@
+CREATE TABLE otp_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    method two_factor_method_enum NOT NULL,
+    purpose VARCHAR(50) NOT NULL,
+    recipient VARCHAR(255) NOT NULL,
+    code_hash BYTEA NOT NULL,
+    attempts INT4 NOT NULL DEFAULT 0,
+    max_attempts INT4 NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX otp_codes_user_id_purpose_idx ON otp_codes (user_id, purpose);
@@ -0,0 +1 @@
+ALTER TABLE accounts ADD COLUMN phone_verified BOOLEAN NOT NULL DEFAULT FALSE;
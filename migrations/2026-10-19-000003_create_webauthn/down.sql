@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS webauthn_challenges;
+DROP TABLE IF EXISTS webauthn_credentials;
+-- Postgres cannot drop a single enum value; 'webauthn' stays in two_factor_method_enum.
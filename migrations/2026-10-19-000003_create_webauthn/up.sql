@@ -0,0 +1,26 @@
+ALTER TYPE two_factor_method_enum ADD VALUE IF NOT EXISTS 'webauthn';
+
+CREATE TABLE webauthn_credentials (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    credential_id BYTEA NOT NULL UNIQUE,
+    public_key BYTEA NOT NULL,
+    sign_count INT8 NOT NULL DEFAULT 0,
+    transports JSON NOT NULL DEFAULT '[]',
+    name VARCHAR(255),
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX webauthn_credentials_user_id_idx ON webauthn_credentials (user_id);
+
+CREATE TABLE webauthn_challenges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID REFERENCES accounts (id) ON DELETE CASCADE,
+    challenge BYTEA NOT NULL,
+    ceremony VARCHAR(50) NOT NULL,
+    -- Set when the ceremony is the second factor of a password login.
+    after_password BOOLEAN NOT NULL DEFAULT FALSE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS magic_links;
+ALTER TABLE accounts DROP COLUMN locked_until;
@@ -0,0 +1,12 @@
+ALTER TABLE accounts ADD COLUMN locked_until TIMESTAMPTZ;
+
+CREATE TABLE magic_links (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    nonce_hash BYTEA NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX magic_links_user_id_idx ON magic_links (user_id);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS accounts_email_lower_key;
+DROP INDEX IF EXISTS accounts_username_lower_key;
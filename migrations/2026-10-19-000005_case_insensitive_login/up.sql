@@ -0,0 +1,6 @@
+-- Emails are stored lower-cased from now on; usernames keep their casing but
+-- must be unique regardless of it. Fails if existing rows already collide.
+UPDATE accounts SET email = LOWER(TRIM(email)), username = TRIM(username);
+
+CREATE UNIQUE INDEX accounts_username_lower_key ON accounts (LOWER(username));
+CREATE UNIQUE INDEX accounts_email_lower_key ON accounts (LOWER(email));
@@ -0,0 +1,6 @@
+DELETE FROM role_permissions
+WHERE role_id IN (SELECT id FROM roles WHERE name = 'admin')
+  AND permission_id IN (SELECT id FROM permissions WHERE name IN ('users', 'rbac'));
+DELETE FROM account_roles WHERE role_id IN (SELECT id FROM roles WHERE name = 'admin');
+DELETE FROM roles WHERE name = 'admin';
+DELETE FROM permissions WHERE name IN ('users', 'rbac');
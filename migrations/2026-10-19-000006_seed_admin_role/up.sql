@@ -0,0 +1,23 @@
+-- Permissions checked by the API and an `admin` role holding all of them.
+-- The first administrator still has to be assigned by hand:
+--   INSERT INTO account_roles (account_id, role_id)
+--   SELECT '<account id>', id FROM roles WHERE name = 'admin';
+INSERT INTO permissions (name, description)
+SELECT v.name, v.description
+FROM (VALUES
+    ('users', 'Manage user accounts'),
+    ('rbac', 'Manage roles, permissions and role assignments')
+) AS v (name, description)
+WHERE NOT EXISTS (SELECT 1 FROM permissions p WHERE p.name = v.name);
+
+INSERT INTO roles (name, description)
+SELECT 'admin', 'Full access to the administration API'
+WHERE NOT EXISTS (SELECT 1 FROM roles WHERE name = 'admin');
+
+INSERT INTO role_permissions (role_id, permission_id, read, write, update, delete)
+SELECT r.id, p.id, TRUE, TRUE, TRUE, TRUE
+FROM roles r
+CROSS JOIN permissions p
+WHERE r.name = 'admin' AND p.name IN ('users', 'rbac')
+ON CONFLICT (role_id, permission_id) DO UPDATE
+SET read = TRUE, write = TRUE, update = TRUE, delete = TRUE;
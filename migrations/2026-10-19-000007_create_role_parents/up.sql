@@ -0,0 +1,10 @@
+-- A role inherits every grant of its parents, transitively.
+CREATE TABLE role_parents (
+    role_id UUID NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+    parent_id UUID NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (role_id, parent_id),
+    CHECK (role_id <> parent_id)
+);
+
+CREATE INDEX role_parents_parent_id_idx ON role_parents (parent_id);
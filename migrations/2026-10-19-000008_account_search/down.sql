@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS accounts_phone_number_trgm_idx;
+DROP INDEX IF EXISTS accounts_email_trgm_idx;
+DROP INDEX IF EXISTS accounts_username_trgm_idx;
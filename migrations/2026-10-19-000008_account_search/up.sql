@@ -0,0 +1,8 @@
+-- Trigram indexes back the substring (LIKE) filters of the account listing
+-- and the admin search, and the search's `%` similarity matches. The
+-- similarity ranking itself is computed only on the rows these select.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX accounts_username_trgm_idx ON accounts USING GIN (LOWER(username) gin_trgm_ops);
+CREATE INDEX accounts_email_trgm_idx ON accounts USING GIN (LOWER(email) gin_trgm_ops);
+CREATE INDEX accounts_phone_number_trgm_idx ON accounts USING GIN (phone_number gin_trgm_ops);
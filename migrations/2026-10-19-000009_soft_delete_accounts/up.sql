@@ -0,0 +1,5 @@
+-- Deleted accounts are kept, hidden, for a grace period in which they can be
+-- restored, and purged for good afterwards.
+ALTER TABLE accounts ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX accounts_deleted_at_idx ON accounts (deleted_at) WHERE deleted_at IS NOT NULL;
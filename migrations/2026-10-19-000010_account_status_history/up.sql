@@ -0,0 +1,13 @@
+-- Every status change, kept for audit even after the account itself is purged,
+-- which is why neither account column references accounts.
+CREATE TABLE account_status_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_id UUID NOT NULL,
+    from_status account_status_enum NOT NULL,
+    to_status account_status_enum NOT NULL,
+    reason TEXT NOT NULL,
+    changed_by UUID,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX account_status_history_account_id_idx ON account_status_history (account_id, changed_at);
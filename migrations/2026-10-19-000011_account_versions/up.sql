@@ -0,0 +1,5 @@
+-- Bumped by the repository whenever an account is edited; exposed as its ETag
+-- so concurrent edits can be detected. Sign-in bookkeeping (attempt counters,
+-- lockouts, last login) leaves it alone, so it does not invalidate the ETag a
+-- client is holding.
+ALTER TABLE accounts ADD COLUMN version INT4 NOT NULL DEFAULT 1;
@@ -0,0 +1,93 @@
+use actix_web::http::header::{self, EntityTag, Header, HeaderValue, IfMatch, IfNoneMatch};
+use actix_web::{HttpRequest, HttpResponse};
+use crate::utils::errors::AppError;
+
+/// Strong ETag for a resource version, e.g. `"7"`.
+pub(crate) fn entity_tag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+/// Versions a write is conditioned on, read from the required `If-Match`
+/// header; `None` for `If-Match: *`, which accepts whatever is current.
+pub(crate) fn expected_versions(req: &HttpRequest) -> Result<Option<Vec<i32>>, AppError> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return Err(AppError::PreconditionRequired(
+            "Send If-Match with the ETag of the account you are changing".to_string(),
+        ));
+    }
+    match IfMatch::parse(req).map_err(|_| AppError::BadRequest("Invalid If-Match header".to_string()))? {
+        IfMatch::Any => Ok(None),
+        // Weak tags never satisfy If-Match, and unknown ones cannot match.
+        IfMatch::Items(tags) => Ok(Some(
+            tags.iter()
+                .filter(|tag| !tag.weak)
+                .filter_map(|tag| tag.tag().parse().ok())
+                .collect(),
+        )),
+    }
+}
+
+/// Whether `If-None-Match` says the client already has `version`.
+pub(crate) fn not_modified(req: &HttpRequest, version: i32) -> bool {
+    if !req.headers().contains_key(header::IF_NONE_MATCH) {
+        return false;
+    }
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&entity_tag(version))),
+        Err(_) => false,
+    }
+}
+
+pub(crate) fn not_modified_response(version: i32) -> HttpResponse {
+    HttpResponse::NotModified()
+        .insert_header(header::ETag(entity_tag(version)))
+        .finish()
+}
+
+/// Adds the ETag of `version` to `response`.
+pub(crate) fn with_etag(mut response: HttpResponse, version: i32) -> HttpResponse {
+    if let Ok(value) = HeaderValue::from_str(&entity_tag(version).to_string()) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+/// Marks `response` as depending on who asked, so caches keep one copy per
+/// caller instead of serving one caller's view of a resource to another.
+pub(crate) fn vary_by_caller(mut response: HttpResponse) -> HttpResponse {
+    response
+        .headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("Authorization"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn if_match_is_required_and_compared_strongly() {
+        let req = TestRequest::default().to_http_request();
+        assert!(matches!(expected_versions(&req), Err(AppError::PreconditionRequired(_))));
+
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "*")).to_http_request();
+        assert_eq!(expected_versions(&req).unwrap(), None);
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, "\"3\", W/\"4\", \"x\""))
+            .to_http_request();
+        assert_eq!(expected_versions(&req).unwrap(), Some(vec![3]));
+    }
+
+    #[test]
+    fn if_none_match_compares_weakly() {
+        let req = TestRequest::default().to_http_request();
+        assert!(!not_modified(&req, 3));
+
+        let req = TestRequest::default().insert_header((header::IF_NONE_MATCH, "W/\"3\"")).to_http_request();
+        assert!(not_modified(&req, 3));
+        assert!(!not_modified(&req, 4));
+    }
+}
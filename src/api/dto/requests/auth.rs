@@ -1,21 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
-pub struct LoginRequest {
-    pub username: String,
-    pub password: String,
+pub struct OtpVerifyRequest {
+    pub challenge_id: uuid::Uuid,
+    pub code: String,
 }
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct RegisterRequest {
-    pub username: String,
-    pub email: String,
-    pub password: String,
-    pub phone_number: Option<String>,
-    pub preferred_language: Option<String>,
-}
-
-
-
-
-
@@ -1 +1,4 @@
 pub mod auth;
+pub mod user;
+pub mod webauthn;
+pub mod rbac;
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::domain::models::policy::PolicyResource;
+use crate::domain::models::rbac::PermissionAction;
+
+/// Actions a role is granted on a permission; omitted flags are off.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GrantRequest {
+    pub read: bool,
+    pub write: bool,
+    pub update: bool,
+    pub delete: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ExplainQuery {
+    /// Defaults to `read`.
+    pub action: Option<PermissionAction>,
+}
+
+/// A policy dry run: would `subject_id` (the caller when omitted) be allowed
+/// to perform `action` on `resource`?
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PolicyEvaluationRequest {
+    pub subject_id: Option<Uuid>,
+    pub action: String,
+    pub resource: PolicyResource,
+}
@@ -0,0 +1,231 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use crate::domain::models::user::{
+    AccountStatusEnum, SortOrder, TwoFactorMethodEnum, UserChangeset, UserSortField,
+};
+use crate::utils::errors::AppError;
+
+/// Body of `POST /api/user/create`. Status, verification and dates are set
+/// by the server.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub email: String,
+    /// Initial password; only its hash is stored.
+    pub password: String,
+    pub phone_number: Option<String>,
+    /// `None` when omitted.
+    pub two_factor_method: Option<TwoFactorMethodEnum>,
+    pub preferred_language: Option<String>,
+}
+
+/// Body of `PUT /api/user/update/{id}`: replaces the editable fields, so an
+/// omitted phone number is removed. The administrative fields are optional
+/// and only accepted from callers allowed to manage accounts.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateUserRequest {
+    pub username: String,
+    pub email: String,
+    pub phone_number: Option<String>,
+    pub two_factor_method: Option<TwoFactorMethodEnum>,
+    pub preferred_language: Option<String>,
+    pub is_active: Option<bool>,
+    pub is_verified: Option<bool>,
+    pub status: Option<AccountStatusEnum>,
+}
+
+impl UpdateUserRequest {
+    pub fn into_changeset(self) -> UserChangeset {
+        UserChangeset {
+            username: Some(self.username),
+            email: Some(self.email),
+            phone_number: Some(self.phone_number),
+            two_factor_method: Some(self.two_factor_method.unwrap_or(TwoFactorMethodEnum::None)),
+            preferred_language: self.preferred_language,
+            is_active: self.is_active,
+            is_verified: self.is_verified,
+            status: self.status,
+            ..Default::default()
+        }
+    }
+}
+
+/// Tells a member set to `null` (`Some(None)`) apart from a missing one (`None`).
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// An RFC 7396 merge patch for `PATCH /api/user/{id}`: members that are
+/// present are changed, `null` removes a value, absent members are untouched.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserPatchRequest {
+    #[serde(default, deserialize_with = "present")]
+    pub username: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub phone_number: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub two_factor_method: Option<Option<TwoFactorMethodEnum>>,
+    #[serde(default, deserialize_with = "present")]
+    pub preferred_language: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub is_active: Option<Option<bool>>,
+    #[serde(default, deserialize_with = "present")]
+    pub is_verified: Option<Option<bool>>,
+    #[serde(default, deserialize_with = "present")]
+    pub status: Option<Option<AccountStatusEnum>>,
+}
+
+/// A member that cannot be removed: `null` is rejected.
+fn required<T>(field: &str, value: Option<Option<T>>) -> Result<Option<T>, AppError> {
+    match value {
+        Some(None) => Err(AppError::ValidationError(format!("{} cannot be removed", field))),
+        value => Ok(value.flatten()),
+    }
+}
+
+impl UserPatchRequest {
+    /// Parses a merge patch document, which has to be a JSON object.
+    pub fn from_merge_patch(body: &[u8]) -> Result<Self, AppError> {
+        let invalid = |e: serde_json::Error| AppError::BadRequest(format!("Invalid merge patch: {}", e));
+        let document: serde_json::Value = serde_json::from_slice(body).map_err(invalid)?;
+        if !document.is_object() {
+            return Err(AppError::BadRequest("A merge patch must be a JSON object".to_string()));
+        }
+        serde_json::from_value(document).map_err(invalid)
+    }
+
+    pub fn into_changeset(self) -> Result<UserChangeset, AppError> {
+        Ok(UserChangeset {
+            username: required("username", self.username)?,
+            email: required("email", self.email)?,
+            phone_number: self.phone_number,
+            // Removing the second factor means switching it off.
+            two_factor_method: self
+                .two_factor_method
+                .map(|method| method.unwrap_or(TwoFactorMethodEnum::None)),
+            preferred_language: required("preferred_language", self.preferred_language)?,
+            is_active: required("is_active", self.is_active)?,
+            is_verified: required("is_verified", self.is_verified)?,
+            status: required("status", self.status)?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Body of the account status endpoints.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StatusChangeRequest {
+    /// Why the status changes; kept in the account's status history.
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PhoneVerificationRequest {
+    /// Channel used to deliver the code, `Sms` when omitted.
+    pub method: Option<TwoFactorMethodEnum>,
+}
+
+/// Fields an account holder may change on their own profile; omitted fields
+/// keep their current value.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ProfileUpdateRequest {
+    pub username: Option<String>,
+    pub email: Option<String>,
+    pub phone_number: Option<String>,
+    pub two_factor_method: Option<TwoFactorMethodEnum>,
+    pub preferred_language: Option<String>,
+}
+
+impl ProfileUpdateRequest {
+    pub fn into_changeset(self) -> UserChangeset {
+        UserChangeset {
+            username: self.username,
+            email: self.email,
+            phone_number: self.phone_number.map(Some),
+            two_factor_method: self.two_factor_method,
+            preferred_language: self.preferred_language,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PaginationMode {
+    /// `page`/`per_page`, with a total count.
+    #[default]
+    Offset,
+    /// Opaque `cursor` tokens taken from the previous page's links.
+    Cursor,
+}
+
+/// Query string of `GET /api/user/all`, e.g.
+/// `?page=2&per_page=20&sort_by=username&order=desc&status=Active&registered_from=2024-01-01`.
+/// Passing `pagination=cursor` or a `cursor` switches to keyset pagination.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UserListQuery {
+    pub pagination: Option<PaginationMode>,
+    pub cursor: Option<String>,
+    /// 1-based, defaults to the first page.
+    pub page: Option<i64>,
+    /// Between 1 and 100, defaults to 20.
+    pub per_page: Option<i64>,
+    pub search: Option<String>,
+    pub sort_by: Option<UserSortField>,
+    pub order: Option<SortOrder>,
+    pub status: Option<AccountStatusEnum>,
+    pub is_active: Option<bool>,
+    pub is_verified: Option<bool>,
+    pub two_factor_method: Option<TwoFactorMethodEnum>,
+    /// Inclusive, `YYYY-MM-DD`.
+    pub registered_from: Option<chrono::NaiveDate>,
+    /// Inclusive, `YYYY-MM-DD`.
+    pub registered_to: Option<chrono::NaiveDate>,
+}
+
+/// Query string of `GET /api/user/search`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserSearchQuery {
+    /// Part of, or something close to, a username, email or phone number.
+    pub q: String,
+    /// Between 1 and 50, defaults to 10.
+    pub limit: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patch(body: &str) -> Result<UserChangeset, AppError> {
+        UserPatchRequest::from_merge_patch(body.as_bytes()).unwrap().into_changeset()
+    }
+
+    #[test]
+    fn merge_patch_changes_only_present_members() {
+        let changes = patch(r#"{"preferred_language": "id"}"#).unwrap();
+        assert_eq!(changes.preferred_language.as_deref(), Some("id"));
+        assert!(changes.username.is_none());
+        assert!(changes.phone_number.is_none());
+        assert!(!changes.changes_managed_fields());
+    }
+
+    #[test]
+    fn merge_patch_null_removes_nullable_members_only() {
+        let changes = patch(r#"{"phone_number": null, "two_factor_method": null}"#).unwrap();
+        assert_eq!(changes.phone_number, Some(None));
+        assert_eq!(changes.two_factor_method, Some(TwoFactorMethodEnum::None));
+        assert!(patch(r#"{"email": null}"#).is_err());
+    }
+
+    #[test]
+    fn merge_patch_rejects_unknown_members() {
+        assert!(UserPatchRequest::from_merge_patch(br#"{"login_attempts": 0}"#).is_err());
+        assert!(UserPatchRequest::from_merge_patch(b"[]").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+// Shapes follow `PublicKeyCredential.toJSON()`; binary values are base64url.
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+    #[serde(default)]
+    pub transports: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistrationCredential {
+    pub id: String,
+    pub raw_id: Option<String>,
+    pub response: AttestationResponse,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RegistrationFinishRequest {
+    pub ceremony_id: uuid::Uuid,
+    pub name: Option<String>,
+    pub credential: RegistrationCredential,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AuthenticationStartRequest {
+    /// Username or email; omit for discoverable-credential (username-less) sign in.
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    pub user_handle: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionCredential {
+    pub id: String,
+    pub raw_id: Option<String>,
+    pub response: AssertionResponse,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AuthenticationFinishRequest {
+    pub ceremony_id: uuid::Uuid,
+    pub credential: AssertionCredential,
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::Serialize;
+use crate::domain::models::user::{AccountStatusEnum, TwoFactorMethodEnum, User};
 
 #[derive(Debug, Serialize)]
 /// Represents a standard API response.
@@ -8,6 +9,7 @@ use serde::Serialize;
 /// - `success`: Indicates if the request was successful.
 /// - `message`: A message providing additional information about the response.
 /// - `data`: Optional data returned by the API.
+/// - `links`: Links to the neighbouring pages of a listing.
 /// - `context`: Contextual information about the response.
 /// - `error`: Optional error information if the request was not successful.
 pub struct ApiResponse<T>{
@@ -15,6 +17,8 @@ pub struct ApiResponse<T>{
     pub message: String,
     #[serde(skip_serializing_if="Option::is_none")]
     pub data: Option<T>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub links: Option<PageLinks>,
     pub context: ResponseContext,
     #[serde(skip_serializing_if="Option::is_none")]
     pub error: Option<ApiError>,
@@ -44,3 +48,271 @@ pub struct ApiError{
 
 
 
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct PageMeta {
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+    pub total_pages: i64,
+    pub has_next: bool,
+    pub has_prev: bool,
+}
+
+impl PageMeta {
+    pub fn new(page: i64, per_page: i64, total: i64) -> Self {
+        let total_pages = (total + per_page - 1) / per_page;
+        PageMeta {
+            page,
+            per_page,
+            total,
+            total_pages,
+            has_next: page < total_pages,
+            has_prev: page > 1,
+        }
+    }
+}
+
+/// One page of a listing together with its position in the whole result.
+#[derive(Debug, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub meta: PageMeta,
+}
+
+impl<T> Paginated<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated { items: self.items.into_iter().map(f).collect(), meta: self.meta }
+    }
+}
+
+/// A listing page read by keyset; the cursors are opaque to clients.
+#[derive(Debug, Serialize)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub per_page: i64,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+impl<T> CursorPage<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> CursorPage<U> {
+        CursorPage {
+            items: self.items.into_iter().map(f).collect(),
+            per_page: self.per_page,
+            next_cursor: self.next_cursor,
+            prev_cursor: self.prev_cursor,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct PageLinks {
+    pub next: Option<String>,
+    pub prev: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserSearchHit {
+    #[serde(flatten)]
+    pub user: AdminUserResponse,
+    /// Trigram similarity of the closest field, between 0 and 1.
+    pub score: f32,
+}
+
+/// An account as its owner sees it. Fields are listed explicitly, so columns
+/// added to `accounts` later stay private until they are added here.
+#[derive(Debug, Serialize)]
+pub struct PublicUserResponse {
+    pub id: uuid::Uuid,
+    pub username: String,
+    pub email: String,
+    pub phone_number: Option<String>,
+    pub phone_verified: bool,
+    pub is_verified: bool,
+    pub two_factor_method: TwoFactorMethodEnum,
+    pub preferred_language: String,
+    pub registration_date: chrono::NaiveDateTime,
+    pub last_login: Option<chrono::NaiveDateTime>,
+}
+
+impl From<User> for PublicUserResponse {
+    fn from(user: User) -> Self {
+        PublicUserResponse {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            phone_number: user.phone_number,
+            phone_verified: user.phone_verified,
+            is_verified: user.is_verified,
+            two_factor_method: user.two_factor_method,
+            preferred_language: user.preferred_language,
+            registration_date: user.registration_date,
+            last_login: user.last_login,
+        }
+    }
+}
+
+/// An account as seen by callers allowed to read any account: the owner's
+/// view plus its administrative state.
+#[derive(Debug, Serialize)]
+pub struct AdminUserResponse {
+    #[serde(flatten)]
+    pub profile: PublicUserResponse,
+    pub is_active: bool,
+    pub status: AccountStatusEnum,
+    pub login_attempts: i32,
+    pub locked_until: Option<chrono::NaiveDateTime>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl From<User> for AdminUserResponse {
+    fn from(user: User) -> Self {
+        AdminUserResponse {
+            is_active: user.is_active,
+            status: user.status,
+            login_attempts: user.login_attempts,
+            locked_until: user.locked_until,
+            deleted_at: user.deleted_at,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            profile: user.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum UserResponse {
+    Public(PublicUserResponse),
+    Admin(AdminUserResponse),
+}
+
+impl UserResponse {
+    /// The view of `user` matching the caller's rights.
+    pub fn new(user: User, admin: bool) -> Self {
+        if admin {
+            UserResponse::Admin(user.into())
+        } else {
+            UserResponse::Public(user.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use crate::domain::models::user::PasswordHash;
+    use crate::domain::models::webauthn::WebauthnCredential;
+
+    /// Whether `$t` implements `Serialize`, decided at compile time: the
+    /// inherent constant only exists when the bound holds and then shadows
+    /// the blanket trait one.
+    macro_rules! serializable {
+        ($t:ty) => {{
+            #[allow(dead_code)]
+            trait NotSerializable {
+                const SERIALIZABLE: bool = false;
+            }
+            impl<T: ?Sized> NotSerializable for T {}
+            struct Probe<T: ?Sized>(std::marker::PhantomData<T>);
+            #[allow(dead_code)]
+            impl<T: ?Sized + Serialize> Probe<T> {
+                const SERIALIZABLE: bool = true;
+            }
+            <Probe<$t>>::SERIALIZABLE
+        }};
+    }
+
+    fn user() -> User {
+        let now = chrono::Utc::now().naive_utc();
+        User {
+            id: uuid::Uuid::new_v4(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            is_active: true,
+            is_verified: true,
+            phone_number: None,
+            status: AccountStatusEnum::Active,
+            last_login: None,
+            two_factor_method: TwoFactorMethodEnum::None,
+            registration_date: now,
+            preferred_language: "en".to_string(),
+            login_attempts: 2,
+            created_at: now,
+            updated_at: now,
+            phone_verified: false,
+            locked_until: None,
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    fn keys(value: impl Serialize) -> BTreeSet<String> {
+        serde_json::to_value(value).unwrap().as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn public_view_hides_administrative_state() {
+        let keys = keys(UserResponse::new(user(), false));
+        for hidden in ["status", "is_active", "login_attempts", "locked_until", "deleted_at", "created_at", "updated_at"] {
+            assert!(!keys.contains(hidden), "{} is exposed", hidden);
+        }
+        assert!(keys.contains("username"));
+    }
+
+    #[test]
+    fn admin_view_adds_administrative_state_only() {
+        let public = keys(UserResponse::new(user(), false));
+        let admin = keys(UserResponse::new(user(), true));
+        let added: BTreeSet<&str> = admin.difference(&public).map(String::as_str).collect();
+        assert_eq!(
+            added,
+            BTreeSet::from([
+                "created_at",
+                "deleted_at",
+                "is_active",
+                "locked_until",
+                "login_attempts",
+                "status",
+                "updated_at",
+            ])
+        );
+    }
+
+    #[test]
+    fn secrets_can_never_be_serialized() {
+        assert!(serializable!(PublicUserResponse));
+        assert!(!serializable!(PasswordHash));
+        assert!(!serializable!(crate::domain::models::user::NewPasswordHash));
+        assert!(!serializable!(crate::domain::models::otp::OtpCode));
+        assert!(!serializable!(crate::domain::models::authentication::MagicLink));
+
+        for view in [UserResponse::new(user(), false), UserResponse::new(user(), true)] {
+            for key in keys(view) {
+                assert!(
+                    !["password", "hash", "salt", "secret", "code"].iter().any(|s| key.contains(s)),
+                    "{} looks sensitive",
+                    key
+                );
+            }
+        }
+
+        let credential = WebauthnCredential {
+            id: uuid::Uuid::new_v4(),
+            user_id: uuid::Uuid::new_v4(),
+            credential_id: vec![1, 2, 3],
+            public_key: vec![4, 5, 6],
+            sign_count: 0,
+            transports: serde_json::Value::Null,
+            name: None,
+            last_used_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        let keys = keys(credential);
+        assert!(!keys.contains("public_key") && !keys.contains("credential_id"));
+    }
+}
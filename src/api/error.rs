@@ -14,6 +14,10 @@ impl ResponseError for AppError {
             AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
             AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
@@ -25,6 +29,10 @@ impl ResponseError for AppError {
             AppError::Unauthorized(message) => ApiResponse::<()>::unauthorized(message.clone()),
             AppError::InternalError(message) => ApiResponse::<()>::internal_error(message.clone(), None),
             AppError::Forbidden(message) => ApiResponse::<()>::forbidden(message.clone(), None),
+            AppError::Conflict(message) => ApiResponse::<()>::conflict(message.clone(), None),
+            AppError::PreconditionFailed(message) => ApiResponse::<()>::precondition_failed(message.clone(), None),
+            AppError::PreconditionRequired(message) => ApiResponse::<()>::precondition_required(message.clone(), None),
+            AppError::TooManyRequests(message) => ApiResponse::<()>::too_many_requests(message.clone(), None),
             AppError::ServiceUnavailable(message) => ApiResponse::<()>::service_unavailable(message.clone(), None),
         }
     }
@@ -45,6 +53,10 @@ where
                 StatusCode::UNPROCESSABLE_ENTITY => AppError::ValidationError("Validation Error".to_string()),
                 StatusCode::UNAUTHORIZED => AppError::Unauthorized("Unauthorized Access".to_string()),
                 StatusCode::FORBIDDEN => AppError::Forbidden("Access Denied".to_string()),
+                StatusCode::CONFLICT => AppError::Conflict("Conflicting request".to_string()),
+                StatusCode::PRECONDITION_FAILED => AppError::PreconditionFailed("Precondition failed".to_string()),
+                StatusCode::PRECONDITION_REQUIRED => AppError::PreconditionRequired("Precondition required".to_string()),
+                StatusCode::TOO_MANY_REQUESTS => AppError::TooManyRequests("Too many requests".to_string()),
                 StatusCode::SERVICE_UNAVAILABLE => AppError::ServiceUnavailable("Service Temporary Unavailable".to_string()),
                 _ => AppError::ServiceUnavailable("Service Temporary Unavailable".to_string()),
             }
@@ -62,11 +74,29 @@ where
         AppError::NotFound(details) => {
             AppError::NotFound(format!("Resource missing: {}", details)).error_response()
         }
+        AppError::ValidationError(details) => {
+            AppError::ValidationError(details).error_response()
+        }
+        AppError::Forbidden(details) => {
+            AppError::Forbidden(details).error_response()
+        }
+        AppError::Conflict(details) => {
+            AppError::Conflict(details).error_response()
+        }
+        AppError::PreconditionFailed(details) => {
+            AppError::PreconditionFailed(details).error_response()
+        }
+        AppError::PreconditionRequired(details) => {
+            AppError::PreconditionRequired(details).error_response()
+        }
+        AppError::TooManyRequests(details) => {
+            AppError::TooManyRequests(details).error_response()
+        }
         AppError::InternalError(details) => {
             AppError::ServiceUnavailable(format!("Server issue: {}", details)).error_response()
         }
-        _ => {
-            AppError::ServiceUnavailable("Unexpected error occurred".to_string()).error_response()
+        AppError::ServiceUnavailable(details) => {
+            AppError::ServiceUnavailable(details).error_response()
         }
     };
 
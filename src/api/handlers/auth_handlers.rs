@@ -1,22 +1,30 @@
-use crate::api::dto::responses::AuthResponse;
+use std::collections::HashMap;
+use crate::api::dto::requests::auth::OtpVerifyRequest;
+use crate::api::dto::responses::{ApiResponse, AuthResponse};
 use crate::config::database::DbPool;
-use crate::domain::models::authentication::LoginRequest;
-use crate::domain::services::authentication;
-use actix_web::{post, web, HttpResponse};
+use crate::domain::models::authentication::{LoginRequest, MagicLinkConsumeRequest, MagicLinkRequest};
+use crate::domain::services::authentication::{self, LoginOutcome};
+use crate::infrastructure::external::otp_sender::OtpSenders;
+use crate::utils::errors::AppError;
+use crate::utils::rate_limit::RateLimiter;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::{post, web, HttpRequest, HttpResponse};
 
-#[post("/token")]
-pub async fn generate_token(
-    pool: web::Data<DbPool>,
-    user: web::Json<LoginRequest>,
-) -> actix_web::Result<HttpResponse> {
-    let user = authentication::token(pool, user)
-        .await
-        .map_err(|e| actix_web::error::ErrorUnauthorized(e.to_string()))?;
+const MAGIC_LINK_NONCE_COOKIE: &str = "magic_link_nonce";
+
+fn client_address(req: &HttpRequest) -> String {
+    req.connection_info()
+        .realip_remote_addr()
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+pub(crate) fn token_response(tokens: HashMap<String, String>) -> actix_web::Result<HttpResponse> {
     let auth = AuthResponse {
-        access_token: user.get("access_token").unwrap().to_string(),
-        refresh_token: user.get("refresh_token").unwrap().to_string(),
-        expires: user.get("expires").unwrap().parse().unwrap(),
-        token_type: Option::from(user.get("token_type").unwrap().to_string()),
+        access_token: tokens.get("access_token").unwrap().to_string(),
+        refresh_token: tokens.get("refresh_token").unwrap().to_string(),
+        expires: tokens.get("expires").unwrap().parse().unwrap(),
+        token_type: Option::from(tokens.get("token_type").unwrap().to_string()),
     };
     Ok(AuthResponse::ok(
         auth.access_token,
@@ -26,6 +34,94 @@ pub async fn generate_token(
     ))
 }
 
+#[post("/token")]
+pub async fn generate_token(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    senders: web::Data<OtpSenders>,
+    limiter: web::Data<RateLimiter>,
+    user: web::Json<LoginRequest>,
+) -> actix_web::Result<HttpResponse> {
+    match authentication::token(pool, senders, limiter, &client_address(&req), user).await? {
+        LoginOutcome::Tokens(tokens) => token_response(tokens),
+        LoginOutcome::MfaRequired(challenge) => Ok(ApiResponse::ok(
+            challenge,
+            "Verification code sent",
+            None,
+        )),
+        LoginOutcome::WebauthnRequired(ceremony) => Ok(ApiResponse::ok(
+            ceremony,
+            "Security key verification required",
+            None,
+        )),
+    }
+}
+
+#[post("/token/otp")]
+pub async fn verify_otp_token(
+    pool: web::Data<DbPool>,
+    payload: web::Json<OtpVerifyRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let payload = payload.into_inner();
+    let tokens = authentication::verify_mfa(pool, payload.challenge_id, &payload.code).await?;
+    token_response(tokens)
+}
+
+#[post("/magic-link")]
+pub async fn request_magic_link(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    senders: web::Data<OtpSenders>,
+    limiter: web::Data<RateLimiter>,
+    payload: web::Json<MagicLinkRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let issued = authentication::request_magic_link(
+        pool,
+        senders,
+        limiter,
+        &client_address(&req),
+        &payload.email,
+    )
+    .await?;
+    let cookie = Cookie::build(MAGIC_LINK_NONCE_COOKIE, issued.device_nonce.clone())
+        .path("/api/auth/magic-link")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .finish();
+    let mut response = ApiResponse::ok(
+        issued,
+        "If the address belongs to an account, a sign in link has been sent",
+        None,
+    );
+    response.add_cookie(&cookie)?;
+    Ok(response)
+}
+
+#[post("/magic-link/consume")]
+pub async fn consume_magic_link(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    limiter: web::Data<RateLimiter>,
+    payload: web::Json<MagicLinkConsumeRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let payload = payload.into_inner();
+    let nonce = payload
+        .device_nonce
+        .or_else(|| req.cookie(MAGIC_LINK_NONCE_COOKIE).map(|c| c.value().to_string()))
+        .ok_or_else(|| AppError::Unauthorized("This link must be opened on the device that requested it".to_string()))?;
+    let tokens = authentication::consume_magic_link(
+        pool,
+        limiter,
+        &client_address(&req),
+        &payload.token,
+        &nonce,
+    )
+    .await?;
+    let mut response = token_response(tokens)?;
+    response.add_removal_cookie(&Cookie::build(MAGIC_LINK_NONCE_COOKIE, "").path("/api/auth/magic-link").finish())?;
+    Ok(response)
+}
+
 // #[post("/register")]
 // pub async fn register_email(
 //     pool :web::Data<DbPool>,
@@ -0,0 +1,15 @@
+use crate::api::dto::responses::ApiResponse;
+use crate::config::database::{self, DbPool};
+use actix_web::{get, web, HttpResponse};
+
+/// Readiness probe: 200 while the database answers, 503 otherwise.
+#[get("/health")]
+pub async fn health(pool: web::Data<DbPool>) -> actix_web::Result<HttpResponse> {
+    database::ping(&pool).await?;
+    let status = pool.status();
+    Ok(ApiResponse::ok(
+        serde_json::json!({ "database": "up", "connections": status.size, "idle": status.available }),
+        "Service is healthy",
+        None,
+    ))
+}
@@ -1,2 +1,5 @@
 pub mod user_handlers;
-pub(crate) mod auth_handlers;
\ No newline at end of file
+pub(crate) mod auth_handlers;
+pub(crate) mod webauthn_handlers;
+pub(crate) mod rbac_handlers;
+pub(crate) mod health_handlers;
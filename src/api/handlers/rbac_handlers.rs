@@ -0,0 +1,381 @@
+use crate::api::dto::requests::rbac::{ExplainQuery, GrantRequest, PolicyEvaluationRequest};
+use crate::api::dto::responses::ApiResponse;
+use crate::api::middlewares::auth::{AuthenticatedUser, RequirePermission};
+use crate::config::database::DbPool;
+use crate::domain::models::rbac::{NewPermission, NewRole, PermissionAction};
+use crate::domain::services::policy_services::{self, PolicyEngine};
+use crate::domain::services::rbac_services;
+use actix_web::{delete, get, post, put, web, HttpResponse};
+
+#[get("/roles", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Read)")]
+pub async fn list_roles(pool: web::Data<DbPool>) -> actix_web::Result<HttpResponse> {
+    let roles = rbac_services::list_roles(pool).await?;
+    Ok(ApiResponse::ok(roles, "Roles fetched successfully", None))
+}
+
+#[get("/roles/{id}", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Read)")]
+pub async fn get_role(
+    pool: web::Data<DbPool>,
+    id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let role = rbac_services::get_role(pool, id.into_inner()).await?;
+    Ok(ApiResponse::ok(role, "Role fetched successfully", None))
+}
+
+#[post("/roles", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Write)")]
+pub async fn create_role(
+    pool: web::Data<DbPool>,
+    role: web::Json<NewRole>,
+) -> actix_web::Result<HttpResponse> {
+    let role = rbac_services::create_role(pool, role.into_inner()).await?;
+    Ok(ApiResponse::created(role, "Role created successfully", None))
+}
+
+#[put("/roles/{id}", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Update)")]
+pub async fn update_role(
+    pool: web::Data<DbPool>,
+    id: web::Path<String>,
+    role: web::Json<NewRole>,
+) -> actix_web::Result<HttpResponse> {
+    let role = rbac_services::update_role(pool, id.into_inner(), role.into_inner()).await?;
+    Ok(ApiResponse::ok(role, "Role updated successfully", None))
+}
+
+#[delete("/roles/{id}", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Delete)")]
+pub async fn delete_role(
+    pool: web::Data<DbPool>,
+    id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    rbac_services::delete_role(pool, id.into_inner()).await?;
+    Ok(ApiResponse::ok((), "Role deleted successfully", None))
+}
+
+#[put(
+    "/roles/{id}/permissions/{permission_id}",
+    wrap = "RequirePermission::new(\"rbac\", PermissionAction::Update)"
+)]
+pub async fn grant_permission(
+    pool: web::Data<DbPool>,
+    path: web::Path<(String, String)>,
+    flags: web::Json<GrantRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let (role_id, permission_id) = path.into_inner();
+    let grant =
+        rbac_services::grant_permission(pool, role_id, permission_id, flags.into_inner()).await?;
+    Ok(ApiResponse::ok(grant, "Permission granted successfully", None))
+}
+
+#[delete(
+    "/roles/{id}/permissions/{permission_id}",
+    wrap = "RequirePermission::new(\"rbac\", PermissionAction::Update)"
+)]
+pub async fn revoke_permission(
+    pool: web::Data<DbPool>,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    let (role_id, permission_id) = path.into_inner();
+    rbac_services::revoke_permission(pool, role_id, permission_id).await?;
+    Ok(ApiResponse::ok((), "Permission revoked successfully", None))
+}
+
+#[put(
+    "/roles/{id}/parents/{parent_id}",
+    wrap = "RequirePermission::new(\"rbac\", PermissionAction::Update)"
+)]
+pub async fn add_parent_role(
+    pool: web::Data<DbPool>,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    let (id, parent_id) = path.into_inner();
+    let role = rbac_services::add_parent(pool, id, parent_id).await?;
+    Ok(ApiResponse::ok(role, "Parent role added successfully", None))
+}
+
+#[delete(
+    "/roles/{id}/parents/{parent_id}",
+    wrap = "RequirePermission::new(\"rbac\", PermissionAction::Update)"
+)]
+pub async fn remove_parent_role(
+    pool: web::Data<DbPool>,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    let (id, parent_id) = path.into_inner();
+    let role = rbac_services::remove_parent(pool, id, parent_id).await?;
+    Ok(ApiResponse::ok(role, "Parent role removed successfully", None))
+}
+
+#[get("/permissions", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Read)")]
+pub async fn list_permissions(pool: web::Data<DbPool>) -> actix_web::Result<HttpResponse> {
+    let permissions = rbac_services::list_permissions(pool).await?;
+    Ok(ApiResponse::ok(permissions, "Permissions fetched successfully", None))
+}
+
+#[post("/permissions", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Write)")]
+pub async fn create_permission(
+    pool: web::Data<DbPool>,
+    permission: web::Json<NewPermission>,
+) -> actix_web::Result<HttpResponse> {
+    let permission = rbac_services::create_permission(pool, permission.into_inner()).await?;
+    Ok(ApiResponse::created(permission, "Permission created successfully", None))
+}
+
+#[put("/permissions/{id}", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Update)")]
+pub async fn update_permission(
+    pool: web::Data<DbPool>,
+    id: web::Path<String>,
+    permission: web::Json<NewPermission>,
+) -> actix_web::Result<HttpResponse> {
+    let permission =
+        rbac_services::update_permission(pool, id.into_inner(), permission.into_inner()).await?;
+    Ok(ApiResponse::ok(permission, "Permission updated successfully", None))
+}
+
+#[delete("/permissions/{id}", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Delete)")]
+pub async fn delete_permission(
+    pool: web::Data<DbPool>,
+    id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    rbac_services::delete_permission(pool, id.into_inner()).await?;
+    Ok(ApiResponse::ok((), "Permission deleted successfully", None))
+}
+
+#[get("/accounts/{id}/roles", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Read)")]
+pub async fn account_roles(
+    pool: web::Data<DbPool>,
+    id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let roles = rbac_services::account_roles(pool, id.into_inner()).await?;
+    Ok(ApiResponse::ok(roles, "Account roles fetched successfully", None))
+}
+
+#[put(
+    "/accounts/{id}/roles/{role_id}",
+    wrap = "RequirePermission::new(\"rbac\", PermissionAction::Update)"
+)]
+pub async fn assign_role(
+    pool: web::Data<DbPool>,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    let (account_id, role_id) = path.into_inner();
+    let roles = rbac_services::assign_role(pool, account_id, role_id).await?;
+    Ok(ApiResponse::ok(roles, "Role assigned successfully", None))
+}
+
+#[delete(
+    "/accounts/{id}/roles/{role_id}",
+    wrap = "RequirePermission::new(\"rbac\", PermissionAction::Update)"
+)]
+pub async fn unassign_role(
+    pool: web::Data<DbPool>,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    let (account_id, role_id) = path.into_inner();
+    let roles = rbac_services::unassign_role(pool, account_id, role_id).await?;
+    Ok(ApiResponse::ok(roles, "Role unassigned successfully", None))
+}
+
+#[get(
+    "/accounts/{id}/permissions/{permission}/explain",
+    wrap = "RequirePermission::new(\"rbac\", PermissionAction::Read)"
+)]
+pub async fn explain_permission(
+    pool: web::Data<DbPool>,
+    path: web::Path<(String, String)>,
+    query: web::Query<ExplainQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let (account_id, permission) = path.into_inner();
+    let action = query.action.unwrap_or(PermissionAction::Read);
+    let explanation =
+        rbac_services::explain_permission(pool, account_id, permission, action).await?;
+    Ok(ApiResponse::ok(explanation, "Permission explained", None))
+}
+
+#[get("/policies", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Read)")]
+pub async fn list_policies(engine: web::Data<PolicyEngine>) -> actix_web::Result<HttpResponse> {
+    Ok(ApiResponse::ok(engine.policies(), "Policies fetched successfully", None))
+}
+
+#[post("/policies/evaluate", wrap = "RequirePermission::new(\"rbac\", PermissionAction::Read)")]
+pub async fn evaluate_policies(
+    pool: web::Data<DbPool>,
+    engine: web::Data<PolicyEngine>,
+    user: AuthenticatedUser,
+    request: web::Json<PolicyEvaluationRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let request = request.into_inner();
+    let subject_id = request.subject_id.unwrap_or(user.id);
+    let decision =
+        policy_services::evaluate(pool, &engine, subject_id, &request.action, request.resource).await?;
+    Ok(ApiResponse::ok(decision, "Policies evaluated", None))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::testing;
+    use actix_web::http::header::AUTHORIZATION;
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+    use serde_json::json;
+
+    #[actix_web::test]
+    async fn admin_endpoints_need_rbac_permissions() {
+        let Some(ctx) = testing::context() else { return };
+        let app = test::init_service(testing::app(&ctx.pool)).await;
+        let user = testing::account(&ctx.pool, "mallory").await;
+        let token = testing::bearer(&ctx.pool, &user).await;
+
+        let req = TestRequest::get().uri("/api/admin/roles").to_request();
+        assert_eq!(testing::send(&app, req).await.0, StatusCode::UNAUTHORIZED);
+
+        let requests = [
+            TestRequest::get().uri("/api/admin/roles"),
+            TestRequest::post().uri("/api/admin/roles").set_json(json!({ "name": "support" })),
+            TestRequest::get().uri("/api/admin/permissions"),
+            TestRequest::put()
+                .uri(&format!("/api/admin/accounts/{}/roles/{}", user.id, uuid::Uuid::new_v4()))
+                .set_json(json!({})),
+        ];
+        for req in requests {
+            let req = req.insert_header((AUTHORIZATION, token.clone())).to_request();
+            assert_eq!(testing::send(&app, req).await.0, StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[actix_web::test]
+    async fn admins_manage_roles_permissions_and_grants() {
+        let Some(ctx) = testing::context() else { return };
+        let app = test::init_service(testing::app(&ctx.pool)).await;
+        let admin = testing::admin(&ctx.pool, "alice").await;
+        let token = testing::bearer(&ctx.pool, &admin).await;
+        let call = |req: TestRequest| req.insert_header((AUTHORIZATION, token.clone())).to_request();
+
+        let (status, body) = testing::send(
+            &app,
+            call(TestRequest::post().uri("/api/admin/roles").set_json(json!({ "name": "support" }))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let role = body["data"]["id"].as_str().unwrap().to_string();
+
+        let (status, _) = testing::send(
+            &app,
+            call(TestRequest::post().uri("/api/admin/roles").set_json(json!({ "name": " " }))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, body) = testing::send(
+            &app,
+            call(
+                TestRequest::put()
+                    .uri(&format!("/api/admin/roles/{}", role))
+                    .set_json(json!({ "name": "helpdesk", "description": "First line" })),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["name"], "helpdesk");
+
+        let (status, body) = testing::send(&app, call(TestRequest::get().uri("/api/admin/roles"))).await;
+        assert_eq!(status, StatusCode::OK);
+        let names: Vec<_> = body["data"].as_array().unwrap().iter().map(|r| r["name"].clone()).collect();
+        assert!(names.contains(&json!("admin")) && names.contains(&json!("helpdesk")));
+
+        let (status, body) = testing::send(
+            &app,
+            call(
+                TestRequest::post()
+                    .uri("/api/admin/permissions")
+                    .set_json(json!({ "name": "tickets" })),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let permission = body["data"]["id"].as_str().unwrap().to_string();
+
+        let grant = format!("/api/admin/roles/{}/permissions/{}", role, permission);
+        let (status, body) = testing::send(
+            &app,
+            call(TestRequest::put().uri(&grant).set_json(json!({ "read": true, "update": true }))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["read"], true);
+        assert_eq!(body["data"]["write"], false);
+
+        let (status, body) =
+            testing::send(&app, call(TestRequest::get().uri(&format!("/api/admin/roles/{}", role)))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["data"]["permissions"],
+            json!([{ "name": "tickets", "read": true, "write": false, "update": true, "delete": false }])
+        );
+
+        let (status, _) = testing::send(&app, call(TestRequest::delete().uri(&grant))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = testing::send(&app, call(TestRequest::delete().uri(&grant))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = testing::send(
+            &app,
+            call(TestRequest::delete().uri(&format!("/api/admin/roles/{}", role))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) =
+            testing::send(&app, call(TestRequest::get().uri(&format!("/api/admin/roles/{}", role)))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn admins_assign_roles_and_link_parents() {
+        let Some(ctx) = testing::context() else { return };
+        let app = test::init_service(testing::app(&ctx.pool)).await;
+        let admin = testing::admin(&ctx.pool, "alice").await;
+        let bob = testing::account(&ctx.pool, "bob").await;
+        let token = testing::bearer(&ctx.pool, &admin).await;
+        let call = |req: TestRequest| req.insert_header((AUTHORIZATION, token.clone())).to_request();
+
+        let mut roles = Vec::new();
+        for name in ["staff", "manager"] {
+            let (_, body) = testing::send(
+                &app,
+                call(TestRequest::post().uri("/api/admin/roles").set_json(json!({ "name": name }))),
+            )
+            .await;
+            roles.push(body["data"]["id"].as_str().unwrap().to_string());
+        }
+        let (staff, manager) = (&roles[0], &roles[1]);
+
+        let assignment = format!("/api/admin/accounts/{}/roles/{}", bob.id, staff);
+        let (status, body) = testing::send(&app, call(TestRequest::put().uri(&assignment))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"][0]["name"], "staff");
+        let (status, body) = testing::send(
+            &app,
+            call(TestRequest::get().uri(&format!("/api/admin/accounts/{}/roles", bob.id))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        let (status, body) = testing::send(&app, call(TestRequest::delete().uri(&assignment))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], json!([]));
+        let (status, _) = testing::send(&app, call(TestRequest::delete().uri(&assignment))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let link = format!("/api/admin/roles/{}/parents/{}", manager, staff);
+        let (status, body) = testing::send(&app, call(TestRequest::put().uri(&link))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["parents"], json!([staff]));
+        let (status, _) = testing::send(
+            &app,
+            call(TestRequest::put().uri(&format!("/api/admin/roles/{}/parents/{}", staff, manager))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, body) = testing::send(&app, call(TestRequest::delete().uri(&link))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["parents"], json!([]));
+    }
+}
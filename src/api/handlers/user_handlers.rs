@@ -1,37 +1,97 @@
 use crate::{config::database::DbPool};
-use actix_web::{delete, get, post, put, web, HttpResponse};
-use crate::api::dto::responses::ApiResponse;
-use crate::domain::models::user::NewUser;
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse};
+use crate::api::dto::requests::auth::OtpVerifyRequest;
+use crate::api::dto::requests::user::{
+    CreateUserRequest, PaginationMode, PhoneVerificationRequest, ProfileUpdateRequest, StatusChangeRequest,
+    UpdateUserRequest, UserListQuery, UserPatchRequest, UserSearchQuery,
+};
+use crate::api::conditional;
+use crate::api::dto::responses::{
+    AdminUserResponse, ApiResponse, PageLinks, PublicUserResponse, UserResponse,
+};
+use crate::api::middlewares::auth::{AuthenticatedUser, RequirePermission};
+use crate::domain::models::rbac::PermissionAction;
+use crate::domain::models::user::{StatusAction, TwoFactorMethodEnum, User};
+use crate::domain::services::policy_services::PolicyEngine;
 use crate::domain::services::user_services;
+use crate::infrastructure::external::otp_sender::OtpSenders;
+use crate::utils::errors::AppError;
 
-#[get("/all")]
-pub async fn list_users(pool: web::Data<DbPool>) -> actix_web::Result<HttpResponse> {
-    // let query = accounts.select(User::as_select());
-    // let mut conn = pool
-    //     .get()
-    //     .map_err(|e| actix_web::error::ErrorServiceUnavailable(e))?;
-    // let users = web::block(move || {
-    //     let data = query.load::<User>(&mut conn).expect("Error loading users");
-    //     data
-    // })
-    //     .await?;
+/// Picks the view of `user` the caller is entitled to.
+async fn user_view(
+    pool: web::Data<DbPool>,
+    caller: &AuthenticatedUser,
+    user: User,
+) -> actix_web::Result<UserResponse> {
+    let admin = caller.has_permission(pool, "users", PermissionAction::Read).await?;
+    Ok(UserResponse::new(user, admin))
+}
+
+/// Link to the same listing with `param` set to `value`, other parameters kept.
+fn page_link(req: &HttpRequest, param: &str, value: &str) -> String {
+    let prefix = format!("{}=", param);
+    let mut query: Vec<&str> = req
+        .query_string()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.starts_with(&prefix))
+        .collect();
+    let pair = format!("{}{}", prefix, value);
+    query.push(&pair);
+    format!("{}?{}", req.path(), query.join("&"))
+}
 
+#[get("/all", wrap = "RequirePermission::new(\"users\", PermissionAction::Read)")]
+pub async fn list_users(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    query: web::Query<UserListQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let query = query.into_inner();
+    if query.cursor.is_some() || query.pagination == Some(PaginationMode::Cursor) {
+        let users = user_services::list_users_by_cursor(pool, query).await?.map(AdminUserResponse::from);
+        let links = PageLinks {
+            next: users.next_cursor.as_deref().map(|c| page_link(&req, "cursor", c)),
+            prev: users.prev_cursor.as_deref().map(|c| page_link(&req, "cursor", c)),
+        };
+        return Ok(ApiResponse::page(users, links, "Users fetched successfully", None));
+    }
+    let users = user_services::list_users(pool, query).await?.map(AdminUserResponse::from);
+    let page = users.meta.page;
+    let links = PageLinks {
+        next: users.meta.has_next.then(|| page_link(&req, "page", &(page + 1).to_string())),
+        prev: users.meta.has_prev.then(|| page_link(&req, "page", &(page - 1).to_string())),
+    };
+    Ok(ApiResponse::page(
+        users,
+        links,
+        "Users fetched successfully",
+        None,
+    ))
+}
+
+#[get("/search", wrap = "RequirePermission::new(\"users\", PermissionAction::Read)")]
+pub async fn search_users(
+    pool: web::Data<DbPool>,
+    query: web::Query<UserSearchQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let users = user_services::search_users(pool, query.into_inner()).await?;
     Ok(ApiResponse::ok(
-        "res_data",
+        users,
         "Users fetched successfully",
         None,
     ))
 }
 
-#[post("/create")]
+#[post("/create", wrap = "RequirePermission::new(\"users\", PermissionAction::Write)")]
 pub async fn create_user(
     pool: web::Data<DbPool>,
-    user: web::Json<NewUser>,
+    user: web::Json<CreateUserRequest>,
 ) -> actix_web::Result<HttpResponse> {
     let userdata = user.into_inner();
     let create_user = user_services::create_user(pool, userdata).await?;
     Ok(ApiResponse::ok(
-        create_user,
+        AdminUserResponse::from(create_user),
         "User created successfully",
         None,
     ))
@@ -39,68 +99,259 @@ pub async fn create_user(
 
 #[get("/users/{id}")]
 pub async fn get_user_by_id(
+    req: HttpRequest,
     pool: web::Data<DbPool>,
+    engine: web::Data<PolicyEngine>,
+    caller: AuthenticatedUser,
     id: web::Path<String>,
 ) -> actix_web::Result<HttpResponse> {
     let id = id.into_inner();
-    let user = user_services::find_user_by_id(pool, id).await?;
-    Ok(ApiResponse::ok(
-        user,
-        "User fetched successfully",
-        None,
+    let user = user_services::find_user_by_id(pool.clone(), &engine, caller.id, id).await?;
+    let version = user.version;
+    // Admins and the owner see different views of the same version.
+    if conditional::not_modified(&req, version) {
+        return Ok(conditional::vary_by_caller(conditional::not_modified_response(version)));
+    }
+    Ok(conditional::vary_by_caller(conditional::with_etag(
+        ApiResponse::ok(user_view(pool, &caller, user).await?, "User fetched successfully", None),
+        version,
+    )))
+}
+
+#[get("/users/by-username/{username}", wrap = "RequirePermission::new(\"users\", PermissionAction::Read)")]
+pub async fn get_user_by_username(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    username: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let user = user_services::find_user_by_username(pool, username.into_inner()).await?;
+    let version = user.version;
+    if conditional::not_modified(&req, version) {
+        return Ok(conditional::not_modified_response(version));
+    }
+    Ok(conditional::with_etag(
+        ApiResponse::ok(AdminUserResponse::from(user), "User fetched successfully", None),
+        version,
+    ))
+}
+
+#[get("/users/by-email/{email}", wrap = "RequirePermission::new(\"users\", PermissionAction::Read)")]
+pub async fn get_user_by_email(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    email: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let user = user_services::find_user_by_email(pool, email.into_inner()).await?;
+    let version = user.version;
+    if conditional::not_modified(&req, version) {
+        return Ok(conditional::not_modified_response(version));
+    }
+    Ok(conditional::with_etag(
+        ApiResponse::ok(AdminUserResponse::from(user), "User fetched successfully", None),
+        version,
     ))
 }
 
 #[put("/update/{id}")]
 pub async fn update_user(
+    req: HttpRequest,
     pool: web::Data<DbPool>,
+    engine: web::Data<PolicyEngine>,
+    caller: AuthenticatedUser,
     id: web::Path<String>,
-    user: web::Json<NewUser>,
+    user: web::Json<UpdateUserRequest>,
 ) -> actix_web::Result<HttpResponse> {
-    let id = id.into_inner();
-    let user = user.into_inner();
-    let updated_user = user_services::update_user(pool, id, user).await?;
-    Ok(ApiResponse::ok(
-        updated_user,
-        "User updated successfully",
-        None,
+    let id =
+        user_services::authorize_account(pool.clone(), &engine, caller.id, "update", id.into_inner()).await?;
+    let expected = conditional::expected_versions(&req)?;
+    let changes = user.into_inner().into_changeset();
+    let updated_user = user_services::apply_changes(pool.clone(), caller.id, id, expected, changes).await?;
+    let version = updated_user.version;
+    Ok(conditional::with_etag(
+        ApiResponse::ok(user_view(pool, &caller, updated_user).await?, "User updated successfully", None),
+        version,
+    ))
+}
+
+/// Reads an `application/merge-patch+json` (or plain JSON) object body.
+fn merge_patch(req: &HttpRequest, body: &[u8]) -> Result<UserPatchRequest, AppError> {
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if !content_type.starts_with("application/merge-patch+json") && !content_type.starts_with("application/json") {
+        return Err(AppError::BadRequest("Expected an application/merge-patch+json body".to_string()));
+    }
+    UserPatchRequest::from_merge_patch(body)
+}
+
+#[patch("/{id}")]
+pub async fn patch_user(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    engine: web::Data<PolicyEngine>,
+    caller: AuthenticatedUser,
+    id: web::Path<String>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    let changes = merge_patch(&req, &body)?.into_changeset()?;
+    let id =
+        user_services::authorize_account(pool.clone(), &engine, caller.id, "update", id.into_inner()).await?;
+    let expected = conditional::expected_versions(&req)?;
+    let updated_user = user_services::apply_changes(pool.clone(), caller.id, id, expected, changes).await?;
+    let version = updated_user.version;
+    Ok(conditional::with_etag(
+        ApiResponse::ok(user_view(pool, &caller, updated_user).await?, "User updated successfully", None),
+        version,
     ))
 }
 
 #[delete("/delete/{id}")]
 pub async fn delete_user(
+    req: HttpRequest,
     pool: web::Data<DbPool>,
+    engine: web::Data<PolicyEngine>,
+    caller: AuthenticatedUser,
     id: web::Path<String>,
 ) -> actix_web::Result<HttpResponse> {
-    let id = id.into_inner();
-    user_services::delete_user(pool, id).await?;
+    let id =
+        user_services::authorize_account(pool.clone(), &engine, caller.id, "delete", id.into_inner()).await?;
+    let expected = conditional::expected_versions(&req)?;
+    user_services::delete_user(pool, caller.id, id, expected).await?;
+    Ok(ApiResponse::ok((), "User deleted successfully", None))
+}
+
+#[post("/accounts/{id}/restore", wrap = "RequirePermission::new(\"users\", PermissionAction::Update)")]
+pub async fn restore_user(
+    pool: web::Data<DbPool>,
+    caller: AuthenticatedUser,
+    id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let user = user_services::restore_user(pool, caller.id, id.into_inner()).await?;
     Ok(ApiResponse::ok(
-        "res_data",
-        "User deleted successfully",
+        AdminUserResponse::from(user),
+        "User restored successfully",
         None,
     ))
 }
 
-// #[get("/users/{id}")]
-// pub async fn get_user_by_id(
-//     pool: web::Data<DbPool>,
-//     uname: web::Path<String>,
-// ) -> actix_web::Result<impl Responder> {
-//     let uname = uname.into_inner();
-//
-//     let user = web::block(move || {
-//         let mut conn = pool.get()?;
-//         accounts
-//             .filter(username.eq(uname))
-//             // .find(user_id)
-//             .select(User::as_select())
-//             .first::<User>(&mut conn)
-//         })
-//         .await?.map_err(actix_web::error::ErrorInternalServerError)?;
-//
-//     Ok(HttpResponse::Ok().json(user))
-// }
-//
+/// `POST /api/admin/accounts/{id}/{suspend|lock|unlock|reactivate}` with a `reason`.
+#[post("/accounts/{id}/{action}", wrap = "RequirePermission::new(\"users\", PermissionAction::Update)")]
+pub async fn change_user_status(
+    pool: web::Data<DbPool>,
+    caller: AuthenticatedUser,
+    path: web::Path<(String, StatusAction)>,
+    body: web::Json<StatusChangeRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let (id, action) = path.into_inner();
+    let user = user_services::change_status(pool, caller.id, id, action, &body.reason).await?;
+    Ok(ApiResponse::ok(
+        AdminUserResponse::from(user),
+        "User status updated successfully",
+        None,
+    ))
+}
+
+#[get("/accounts/{id}/status-history", wrap = "RequirePermission::new(\"users\", PermissionAction::Read)")]
+pub async fn user_status_history(
+    pool: web::Data<DbPool>,
+    id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let history = user_services::status_history(pool, id.into_inner()).await?;
+    Ok(ApiResponse::ok(
+        history,
+        "Status history fetched successfully",
+        None,
+    ))
+}
+
+#[get("/me")]
+pub async fn get_me(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    caller: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user = user_services::find_account(pool.clone(), caller.id).await?;
+    let version = user.version;
+    if conditional::not_modified(&req, version) {
+        return Ok(conditional::not_modified_response(version));
+    }
+    Ok(conditional::with_etag(
+        ApiResponse::ok(user_view(pool, &caller, user).await?, "User fetched successfully", None),
+        version,
+    ))
+}
+
+#[put("/me")]
+pub async fn update_me(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    engine: web::Data<PolicyEngine>,
+    caller: AuthenticatedUser,
+    profile: web::Json<ProfileUpdateRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let id =
+        user_services::authorize_account(pool.clone(), &engine, caller.id, "update", caller.id.to_string()).await?;
+    let expected = conditional::expected_versions(&req)?;
+    let user =
+        user_services::apply_changes(pool.clone(), id, id, expected, profile.into_inner().into_changeset()).await?;
+    let version = user.version;
+    Ok(conditional::with_etag(
+        ApiResponse::ok(user_view(pool, &caller, user).await?, "Profile updated successfully", None),
+        version,
+    ))
+}
+
+#[delete("/me")]
+pub async fn delete_me(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    engine: web::Data<PolicyEngine>,
+    caller: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse> {
+    let id =
+        user_services::authorize_account(pool.clone(), &engine, caller.id, "delete", caller.id.to_string()).await?;
+    let expected = conditional::expected_versions(&req)?;
+    user_services::delete_user(pool, caller.id, id, expected).await?;
+    Ok(ApiResponse::ok(
+        (),
+        "Account deleted successfully",
+        None,
+    ))
+}
+
+#[post("/me/verify-phone")]
+pub async fn request_phone_verification(
+    pool: web::Data<DbPool>,
+    senders: web::Data<OtpSenders>,
+    caller: AuthenticatedUser,
+    payload: web::Json<PhoneVerificationRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let method = payload.method.unwrap_or(TwoFactorMethodEnum::Sms);
+    let challenge = user_services::request_phone_verification(pool, &senders, caller.id, method).await?;
+    Ok(ApiResponse::ok(
+        challenge,
+        "Verification code sent",
+        None,
+    ))
+}
+
+#[post("/me/verify-phone/confirm")]
+pub async fn confirm_phone_verification(
+    pool: web::Data<DbPool>,
+    caller: AuthenticatedUser,
+    payload: web::Json<OtpVerifyRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let payload = payload.into_inner();
+    let user =
+        user_services::confirm_phone_verification(pool, caller.id, payload.challenge_id, &payload.code).await?;
+    Ok(ApiResponse::ok(
+        PublicUserResponse::from(user),
+        "Phone number verified successfully",
+        None,
+    ))
+}
 
 // #[cfg(test)]
 // mod tests {
@@ -170,3 +421,115 @@ pub async fn delete_user(
 //         assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::testing;
+    use actix_web::http::header::{AUTHORIZATION, IF_MATCH, VARY};
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+    use serde_json::json;
+
+    #[actix_web::test]
+    async fn only_owners_and_account_managers_change_an_account() {
+        let Some(ctx) = testing::context() else { return };
+        let app = test::init_service(testing::app(&ctx.pool)).await;
+        let bob = testing::account(&ctx.pool, "bob").await;
+        let carol = testing::account(&ctx.pool, "carol").await;
+        let carol_token = testing::bearer(&ctx.pool, &carol).await;
+        let bob_token = testing::bearer(&ctx.pool, &bob).await;
+
+        let req = TestRequest::put()
+            .uri(&format!("/api/user/update/{}", bob.id))
+            .insert_header((AUTHORIZATION, carol_token.clone()))
+            .insert_header((IF_MATCH, "*"))
+            .set_json(json!({ "username": "carol2", "email": "carol2@example.com" }))
+            .to_request();
+        assert_eq!(testing::send(&app, req).await.0, StatusCode::FORBIDDEN);
+
+        let delete = format!("/api/user/delete/{}", bob.id);
+        let req = TestRequest::delete()
+            .uri(&delete)
+            .insert_header((AUTHORIZATION, carol_token))
+            .insert_header((IF_MATCH, "*"))
+            .to_request();
+        assert_eq!(testing::send(&app, req).await.0, StatusCode::FORBIDDEN);
+
+        let req = TestRequest::delete()
+            .uri(&delete)
+            .insert_header((AUTHORIZATION, bob_token))
+            .insert_header((IF_MATCH, "*"))
+            .to_request();
+        let (status, body) = testing::send(&app, req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], serde_json::Value::Null);
+    }
+
+    #[actix_web::test]
+    async fn users_are_fetched_by_id_in_the_callers_view() {
+        let Some(ctx) = testing::context() else { return };
+        let app = test::init_service(testing::app(&ctx.pool)).await;
+        let admin = testing::admin(&ctx.pool, "alice").await;
+        let bob = testing::account(&ctx.pool, "bob").await;
+        let carol = testing::account(&ctx.pool, "carol").await;
+        let get = |token: &str, id: String| {
+            TestRequest::get()
+                .uri(&format!("/api/user/users/{}", id))
+                .insert_header((AUTHORIZATION, token.to_string()))
+                .to_request()
+        };
+
+        let token = testing::bearer(&ctx.pool, &bob).await;
+        let response = test::call_service(&app, get(&token, bob.id.to_string())).await;
+        assert_eq!(response.headers().get(VARY).unwrap(), "Authorization");
+        let (status, body) = testing::send(&app, get(&token, bob.id.to_string())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["username"], "bob");
+        assert!(body["data"].get("status").is_none());
+        let (status, _) = testing::send(&app, get(&token, carol.id.to_string())).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let token = testing::bearer(&ctx.pool, &admin).await;
+        let (status, body) = testing::send(&app, get(&token, bob.id.to_string())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "Active");
+        let (status, _) = testing::send(&app, get(&token, uuid::Uuid::new_v4().to_string())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn users_are_looked_up_by_username_and_email_ignoring_case() {
+        let Some(ctx) = testing::context() else { return };
+        let app = test::init_service(testing::app(&ctx.pool)).await;
+        let admin = testing::admin(&ctx.pool, "alice").await;
+        let bob = testing::account(&ctx.pool, "bob").await;
+        let token = testing::bearer(&ctx.pool, &admin).await;
+        let get = |uri: &str| {
+            TestRequest::get()
+                .uri(uri)
+                .insert_header((AUTHORIZATION, token.clone()))
+                .to_request()
+        };
+
+        for uri in [
+            "/api/user/users/by-username/bob",
+            "/api/user/users/by-username/BoB",
+            "/api/user/users/by-email/bob@example.com",
+            "/api/user/users/by-email/BOB@Example.com",
+        ] {
+            let (status, body) = testing::send(&app, get(uri)).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            assert_eq!(body["data"]["id"], bob.id.to_string());
+        }
+        for uri in ["/api/user/users/by-username/robert", "/api/user/users/by-email/robert@example.com"] {
+            assert_eq!(testing::send(&app, get(uri)).await.0, StatusCode::NOT_FOUND, "{}", uri);
+        }
+
+        let token = testing::bearer(&ctx.pool, &bob).await;
+        let req = TestRequest::get()
+            .uri("/api/user/users/by-username/alice")
+            .insert_header((AUTHORIZATION, token))
+            .to_request();
+        assert_eq!(testing::send(&app, req).await.0, StatusCode::FORBIDDEN);
+    }
+}
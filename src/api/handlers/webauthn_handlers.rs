@@ -0,0 +1,68 @@
+use crate::api::dto::requests::webauthn::{
+    AuthenticationFinishRequest, AuthenticationStartRequest, RegistrationFinishRequest,
+};
+use crate::api::dto::responses::ApiResponse;
+use crate::api::handlers::auth_handlers::token_response;
+use crate::api::middlewares::auth::AuthenticatedUser;
+use crate::config::database::DbPool;
+use crate::domain::services::webauthn_services;
+use actix_web::{post, web, HttpResponse};
+
+#[post("/register/start")]
+pub async fn start_registration(
+    pool: web::Data<DbPool>,
+    caller: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse> {
+    let options =
+        webauthn_services::start_registration(pool, caller.id, caller.signed_in_at, &caller.methods).await?;
+    Ok(ApiResponse::ok(
+        options,
+        "Registration options created",
+        None,
+    ))
+}
+
+#[post("/register/finish")]
+pub async fn finish_registration(
+    pool: web::Data<DbPool>,
+    caller: AuthenticatedUser,
+    payload: web::Json<RegistrationFinishRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let credential = webauthn_services::finish_registration(
+        pool,
+        caller.id,
+        caller.signed_in_at,
+        &caller.methods,
+        payload.into_inner(),
+    )
+    .await?;
+    Ok(ApiResponse::created(
+        credential,
+        "Security key registered successfully",
+        None,
+    ))
+}
+
+#[post("/login/start")]
+pub async fn start_authentication(
+    pool: web::Data<DbPool>,
+    payload: web::Json<AuthenticationStartRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let options = webauthn_services::start_authentication(pool, payload.into_inner().username).await?;
+    Ok(ApiResponse::ok(
+        options,
+        "Authentication options created",
+        None,
+    ))
+}
+
+#[post("/login/finish")]
+pub async fn finish_authentication(
+    pool: web::Data<DbPool>,
+    payload: web::Json<AuthenticationFinishRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let payload = payload.into_inner();
+    let tokens =
+        webauthn_services::finish_authentication(pool, payload.ceremony_id, payload.credential).await?;
+    token_response(tokens)
+}
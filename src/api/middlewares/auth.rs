@@ -0,0 +1,189 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest};
+use chrono::{DateTime, NaiveDateTime};
+use pasetors::claims::Claims;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::domain::models::rbac::{AccessGrants, EffectivePermission, PermissionAction};
+use crate::domain::services::authentication::{ensure_token_usable, ACCESS_TOKEN_AUDIENCE};
+use crate::domain::services::permission_services::{missing_permission, PermissionService};
+use crate::utils::crypto::decrypt_token;
+use crate::utils::errors::AppError;
+
+/// The account behind the request's `Authorization: Bearer` access token.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub id: Uuid,
+    /// Roles and permissions embedded in the token, if it carries them.
+    pub access: Option<AccessGrants>,
+    /// When the caller signed in, which is when the token was issued.
+    pub signed_in_at: NaiveDateTime,
+    /// Sign in methods from the token's `amr` claim.
+    pub methods: Vec<String>,
+}
+
+impl AuthenticatedUser {
+    /// Checks a grant against the token's embedded permissions, or the
+    /// database when the token carries none.
+    pub async fn has_permission(
+        &self,
+        pool: web::Data<DbPool>,
+        permission: &str,
+        action: PermissionAction,
+    ) -> Result<bool, Error> {
+        match &self.access {
+            Some(access) => Ok(access.allows(permission, action)),
+            None => PermissionService::new(pool).has_permission(self.id, permission, action).await,
+        }
+    }
+}
+
+fn string_list(value: Option<&serde_json::Value>) -> Option<Vec<String>> {
+    value?
+        .as_array()?
+        .iter()
+        .map(|item| item.as_str().map(str::to_string))
+        .collect()
+}
+
+fn embedded_access(claims: &Claims) -> Option<AccessGrants> {
+    let roles = string_list(claims.get_claim("roles"))?;
+    let permissions = string_list(claims.get_claim("permissions"))?
+        .iter()
+        .map(|p| EffectivePermission::from_compact(p))
+        .collect::<Option<Vec<_>>>()?;
+    Some(AccessGrants { roles, permissions })
+}
+
+/// The caller named by the request's access token and when that token was issued.
+fn decode_token(req: &HttpRequest) -> Result<AuthenticatedUser, AppError> {
+    let unauthorized = || AppError::Unauthorized("Missing or invalid access token".to_string());
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(unauthorized)?;
+    let claims = decrypt_token(token.trim()).map_err(|_| unauthorized())?;
+    let claim = |name: &str| claims.get_claim(name).and_then(|v| v.as_str());
+    if claim("aud") != Some(ACCESS_TOKEN_AUDIENCE) {
+        return Err(unauthorized());
+    }
+    let id = claim("sub")
+        .and_then(|sub| Uuid::parse_str(sub).ok())
+        .ok_or_else(unauthorized)?;
+    let issued_at = claim("iat")
+        .and_then(|iat| DateTime::parse_from_rfc3339(iat).ok())
+        .ok_or_else(unauthorized)?
+        .naive_utc();
+    Ok(AuthenticatedUser {
+        id,
+        access: embedded_access(&claims),
+        signed_in_at: issued_at,
+        methods: string_list(claims.get_claim("amr")).unwrap_or_default(),
+    })
+}
+
+/// Decodes the access token and checks it was not revoked since, once per request.
+async fn authenticate(req: &HttpRequest) -> Result<AuthenticatedUser, Error> {
+    if let Some(user) = req.extensions().get::<AuthenticatedUser>() {
+        return Ok(user.clone());
+    }
+    let user = decode_token(req)?;
+    let pool = req
+        .app_data::<web::Data<DbPool>>()
+        .cloned()
+        .ok_or_else(|| AppError::ServiceUnavailable("Database unavailable".to_string()))?;
+    ensure_token_usable(pool, user.id, user.signed_in_at).await?;
+    req.extensions_mut().insert(user.clone());
+    Ok(user)
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { authenticate(&req).await })
+    }
+}
+
+/// Rejects requests whose caller lacks `action` on `permission`, e.g.
+/// `#[get("/all", wrap = "RequirePermission::new(\"users\", PermissionAction::Read)")]`.
+/// Unauthenticated callers get `401`, authenticated ones without the grant `403`.
+/// Permissions embedded in the token are trusted as is; otherwise they are
+/// resolved (and cached) from the database.
+pub struct RequirePermission {
+    permission: &'static str,
+    action: PermissionAction,
+}
+
+impl RequirePermission {
+    pub fn new(permission: &'static str, action: PermissionAction) -> Self {
+        RequirePermission { permission, action }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequirePermissionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: self.permission,
+            action: self.action,
+        }))
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: &'static str,
+    action: PermissionAction,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let permission = self.permission;
+        let action = self.action;
+        Box::pin(async move {
+            let user = authenticate(req.request()).await?;
+            match &user.access {
+                Some(access) if access.allows(permission, action) => {}
+                Some(_) => return Err(missing_permission(permission, action).into()),
+                None => {
+                    let pool = req
+                        .app_data::<web::Data<DbPool>>()
+                        .cloned()
+                        .ok_or_else(|| AppError::ServiceUnavailable("Database unavailable".to_string()))?;
+                    PermissionService::new(pool).require(user.id, permission, action).await?;
+                }
+            }
+            service.call(req).await
+        })
+    }
+}
@@ -1,5 +1,9 @@
 pub mod routes;
+mod conditional;
 mod handlers;
+pub mod middlewares;
 pub(crate) mod dto;
 mod response;
-pub(crate) mod error;
\ No newline at end of file
+pub(crate) mod error;
+#[cfg(test)]
+pub(crate) mod testing;
@@ -1,7 +1,7 @@
 use actix_web::HttpResponse;
 use chrono::Utc;
 use serde::Serialize;
-use crate::api::dto::responses::{ApiError, ApiResponse, AuthResponse, ResponseContext};
+use crate::api::dto::responses::{ApiError, ApiResponse, AuthResponse, PageLinks, ResponseContext};
 
 impl AuthResponse {
     pub fn ok(access_token: String, refresh_token: String, expires: i64, token_type: String) -> HttpResponse {
@@ -23,6 +23,22 @@ impl<T: Serialize> ApiResponse<T> {
             success: true,
             message: message.into(),
             data: Some(data),
+            links: None,
+            context: ResponseContext {
+                timestamp: Utc::now(),
+                user,
+            },
+            error: None,
+        })
+    }
+
+    // 200 OK with a page of a listing
+    pub fn page(data: T, links: PageLinks, message: impl Into<String>, user: Option<String>) -> HttpResponse {
+        HttpResponse::Ok().json(Self {
+            success: true,
+            message: message.into(),
+            data: Some(data),
+            links: Some(links),
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user,
@@ -37,6 +53,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: true,
             message: message.into(),
             data: Some(data),
+            links: None,
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user,
@@ -57,6 +74,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             message: message.clone(),
             data: None,
+            links: None,
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user,
@@ -76,6 +94,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             message: message.clone(),
             data: None,
+            links: None,
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user: None,
@@ -95,6 +114,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             message: message.clone(),
             data: None,
+            links: None,
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user,
@@ -107,6 +127,27 @@ impl<T: Serialize> ApiResponse<T> {
         })
     }
 
+    // 409 Conflict
+    pub fn conflict(message: impl Into<String>, user: Option<String>) -> HttpResponse {
+        let message = message.into();
+
+        HttpResponse::Conflict().json(ApiResponse::<()> {
+            success: false,
+            message: message.clone(),
+            data: None,
+            links: None,
+            context: ResponseContext {
+                timestamp: Utc::now(),
+                user,
+            },
+            error: Some(ApiError {
+                code: "CONFLICT".to_string(),
+                message: message.clone(),
+                details: None,
+            }),
+        })
+    }
+
     // 404 Not Found
     pub fn not_found(message: impl Into<String>, user: Option<String>) -> HttpResponse {
         let message = message.into();
@@ -115,6 +156,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             message: message.clone(),
             data: None,
+            links: None,
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user,
@@ -127,6 +169,27 @@ impl<T: Serialize> ApiResponse<T> {
         })
     }
 
+    // 412 Precondition Failed
+    pub fn precondition_failed(message: impl Into<String>, user: Option<String>) -> HttpResponse {
+        let message = message.into();
+
+        HttpResponse::PreconditionFailed().json(ApiResponse::<()> {
+            success: false,
+            message: message.clone(),
+            data: None,
+            links: None,
+            context: ResponseContext {
+                timestamp: Utc::now(),
+                user,
+            },
+            error: Some(ApiError {
+                code: "PRECONDITION_FAILED".to_string(),
+                message: message.clone(),
+                details: None,
+            }),
+        })
+    }
+
     // 422 Unprocessable Entity
     pub fn validation_error(message: impl Into<String>, details: serde_json::Value, user: Option<String>) -> HttpResponse {
         let message = message.into();
@@ -135,6 +198,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             message: message.clone(),
             data: None,
+            links: None,
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user,
@@ -147,6 +211,48 @@ impl<T: Serialize> ApiResponse<T> {
         })
     }
 
+    // 428 Precondition Required
+    pub fn precondition_required(message: impl Into<String>, user: Option<String>) -> HttpResponse {
+        let message = message.into();
+
+        HttpResponse::PreconditionRequired().json(ApiResponse::<()> {
+            success: false,
+            message: message.clone(),
+            data: None,
+            links: None,
+            context: ResponseContext {
+                timestamp: Utc::now(),
+                user,
+            },
+            error: Some(ApiError {
+                code: "PRECONDITION_REQUIRED".to_string(),
+                message: message.clone(),
+                details: None,
+            }),
+        })
+    }
+
+    // 429 Too Many Requests
+    pub fn too_many_requests(message: impl Into<String>, user: Option<String>) -> HttpResponse {
+        let message = message.into();
+
+        HttpResponse::TooManyRequests().json(ApiResponse::<()> {
+            success: false,
+            message: message.clone(),
+            data: None,
+            links: None,
+            context: ResponseContext {
+                timestamp: Utc::now(),
+                user,
+            },
+            error: Some(ApiError {
+                code: "TOO_MANY_REQUESTS".to_string(),
+                message: message.clone(),
+                details: None,
+            }),
+        })
+    }
+
     // 500 Internal Server Error
     pub fn internal_error(message: impl Into<String>, user: Option<String>) -> HttpResponse {
         let message = message.into();
@@ -155,6 +261,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             message: message.clone(),
             data: None,
+            links: None,
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user,
@@ -175,6 +282,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             message: message.clone(),
             data: None,
+            links: None,
             context: ResponseContext {
                 timestamp: Utc::now(),
                 user,
@@ -1,18 +1,78 @@
 use actix_web::web;
-use crate::api::handlers::user_handlers::{create_user, delete_user, list_users, update_user};
-use crate::api::handlers::auth_handlers::{generate_token};
+use crate::api::handlers::user_handlers::{
+    change_user_status, confirm_phone_verification, create_user, delete_me, delete_user, get_me, get_user_by_email,
+    get_user_by_id, get_user_by_username, list_users, patch_user,
+    request_phone_verification, restore_user, search_users, update_me, update_user, user_status_history,
+};
+use crate::api::handlers::auth_handlers::{
+    consume_magic_link, generate_token, request_magic_link, verify_otp_token,
+};
+use crate::api::handlers::rbac_handlers::{
+    account_roles, add_parent_role, assign_role, create_permission, create_role, delete_permission,
+    delete_role, evaluate_policies, explain_permission, get_role, grant_permission, list_permissions,
+    list_policies, list_roles,
+    remove_parent_role, revoke_permission, unassign_role, update_permission, update_role,
+};
+use crate::api::handlers::health_handlers::health;
+use crate::api::handlers::webauthn_handlers::{
+    finish_authentication, finish_registration, start_authentication, start_registration,
+};
 
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
+            .service(health)
             .service(web::scope("/user")
                 .service(list_users)
+                .service(search_users)
+                .service(get_user_by_username)
+                .service(get_user_by_email)
+                .service(get_user_by_id)
                 .service(create_user)
                 .service(update_user)
                 .service(delete_user)
+                .service(get_me)
+                .service(update_me)
+                .service(delete_me)
+                .service(request_phone_verification)
+                .service(confirm_phone_verification)
+                .service(patch_user)
             )
             .service(web::scope("/auth")
                 .service(generate_token)
+                .service(verify_otp_token)
+                .service(request_magic_link)
+                .service(consume_magic_link)
+            )
+            .service(web::scope("/webauthn")
+                .service(start_registration)
+                .service(finish_registration)
+                .service(start_authentication)
+                .service(finish_authentication)
+            )
+            .service(web::scope("/admin")
+                .service(list_roles)
+                .service(get_role)
+                .service(create_role)
+                .service(update_role)
+                .service(delete_role)
+                .service(grant_permission)
+                .service(revoke_permission)
+                .service(add_parent_role)
+                .service(remove_parent_role)
+                .service(list_permissions)
+                .service(create_permission)
+                .service(update_permission)
+                .service(delete_permission)
+                .service(account_roles)
+                .service(assign_role)
+                .service(unassign_role)
+                .service(explain_permission)
+                .service(list_policies)
+                .service(evaluate_policies)
+                .service(restore_user)
+                .service(change_user_status)
+                .service(user_status_history)
             )
     );
 }
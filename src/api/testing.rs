@@ -0,0 +1,111 @@
+use actix_web::body::{self, MessageBody};
+use actix_web::dev::{Service, ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::{web, App, Error};
+use diesel::sql_types::Uuid as SqlUuid;
+use diesel_async::RunQueryDsl;
+
+use crate::api::routes;
+use crate::config::database::{build_pool, DbPool, POOL_SETTINGS};
+use crate::config::{error_handling, otp, policy, security};
+use crate::domain::models::user::{AccountStatusEnum, NewUser, TwoFactorMethodEnum, User};
+use crate::domain::repositories::user_repository::UserRepository;
+use crate::domain::services::authentication::{issue_tokens, AMR_PASSWORD};
+use crate::infrastructure::database::test_database::TestDatabase;
+
+/// A migrated throwaway database and a pool on it. The pool is declared first
+/// so it closes its connections before the database is dropped.
+pub(crate) struct TestContext {
+    pub(crate) pool: web::Data<DbPool>,
+    _database: TestDatabase,
+}
+
+/// `None` when `TEST_DATABASE_URL` is not set and the test should be skipped.
+pub(crate) fn context() -> Option<TestContext> {
+    let database = TestDatabase::migrated()?;
+    let pool = build_pool(&database.url, &POOL_SETTINGS).unwrap();
+    Some(TestContext { pool: web::Data::new(pool), _database: database })
+}
+
+/// The application as `main` assembles it, on the given pool.
+pub(crate) fn app(
+    pool: &web::Data<DbPool>,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    App::new()
+        .app_data(pool.clone())
+        .app_data(web::Data::new(otp::init_otp_senders()))
+        .app_data(web::Data::new(security::init_login_rate_limiter()))
+        .app_data(web::Data::new(policy::init_policy_engine()))
+        .wrap(error_handling::init_error_handlers())
+        .configure(routes::user_routes::init)
+}
+
+/// Sends `req` and returns the status with the JSON body, `Null` when the
+/// body is empty; errors are turned into their responses like the server does.
+pub(crate) async fn send<S, R, B>(app: &S, req: R) -> (StatusCode, serde_json::Value)
+where
+    S: Service<R, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let (status, bytes) = match app.call(req).await {
+        Ok(response) => {
+            let status = response.status();
+            (status, body::to_bytes(response.into_body()).await.ok().unwrap_or_default())
+        }
+        Err(e) => {
+            let response = e.error_response();
+            let status = response.status();
+            (status, body::to_bytes(response.into_body()).await.ok().unwrap_or_default())
+        }
+    };
+    (status, serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null))
+}
+
+/// An active account named `username`, without roles or a password.
+pub(crate) async fn account(pool: &web::Data<DbPool>, username: &str) -> User {
+    let mut conn = pool.get().await.unwrap();
+    UserRepository::insert_account(
+        &mut conn,
+        &NewUser {
+            username: username.to_string(),
+            email: format!("{}@example.com", username),
+            phone_number: None,
+            is_active: true,
+            is_verified: true,
+            registration_date: chrono::Utc::now().naive_utc(),
+            last_login: None,
+            two_factor_method: TwoFactorMethodEnum::None,
+            preferred_language: None,
+            status: AccountStatusEnum::Active,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+/// An account holding the seeded `admin` role.
+pub(crate) async fn admin(pool: &web::Data<DbPool>, username: &str) -> User {
+    let user = account(pool, username).await;
+    let mut conn = pool.get().await.unwrap();
+    diesel::sql_query("INSERT INTO account_roles (account_id, role_id) SELECT $1, id FROM roles WHERE name = 'admin'")
+        .bind::<SqlUuid, _>(user.id)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    user
+}
+
+/// An `Authorization` header value signing in as `user` with a password.
+pub(crate) async fn bearer(pool: &web::Data<DbPool>, user: &User) -> String {
+    std::env::set_var("TOKEN_SECRET_KEY", "mL7h0mMOsML8DRNXfqGcc57j+AWnzTws9jgujQxq0xs=");
+    let tokens = issue_tokens(pool.clone(), user, &[AMR_PASSWORD]).await.unwrap();
+    format!("Bearer {}", tokens["access_token"])
+}
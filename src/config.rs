@@ -1,7 +1,18 @@
+pub mod accounts;
 pub mod database;
 pub mod error_handling;
+pub mod otp;
+pub mod policy;
+pub mod security;
+pub mod webauthn;
 
 use std::env;
 pub fn get_database_url() -> String {
     env::var("DATABASE_URL").expect("DATABASE_URL must be set")
-}
\ No newline at end of file
+}
+
+/// Reads and parses an environment variable, falling back to `default` when
+/// it is missing or malformed.
+pub(crate) fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
@@ -0,0 +1,16 @@
+use once_cell::sync::Lazy;
+use crate::config::env_or;
+
+/// How long soft-deleted accounts are kept before they are purged for good.
+#[derive(Debug, Clone)]
+pub struct RetentionSettings {
+    /// Days after deletion during which an administrator can restore the account.
+    pub restore_grace_days: i64,
+    /// How often the purge of expired accounts runs.
+    pub purge_interval_minutes: u64,
+}
+
+pub static RETENTION_SETTINGS: Lazy<RetentionSettings> = Lazy::new(|| RetentionSettings {
+    restore_grace_days: env_or("ACCOUNT_RESTORE_GRACE_DAYS", 30),
+    purge_interval_minutes: env_or("ACCOUNT_PURGE_INTERVAL_MINUTES", 60),
+});
@@ -1,29 +1,365 @@
-use diesel::r2d2::ConnectionManager;
-use diesel::{   PgConnection};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use deadpool::managed::TimeoutType;
+use deadpool::Runtime;
+use diesel::{ConnectionError, ConnectionResult};
+use diesel_async::pooled_connection::deadpool::{Hook, HookError, Pool, PoolError};
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
+use diesel_async::AsyncPgConnection;
 use dotenvy::dotenv;
+use futures_util::future::{join_all, BoxFuture, FutureExt};
+use once_cell::sync::Lazy;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use rustls::{ClientConfig, RootCertStore};
 use std::env;
+use tokio_postgres::config::{Host, SslMode as PgSslMode};
+use tokio_postgres::NoTls;
+use tokio_postgres_rustls::MakeRustlsConnect;
+use crate::config::env_or;
 use crate::utils::errors::AppError;
 
 // Database connection configuration
-pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
+pub type DbPool = Pool<AsyncPgConnection>;
+
+/// Whether connections use TLS, named after libpq's `sslmode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SslMode {
+    /// Plain connections only.
+    Disable,
+    /// TLS when the server offers it, plain otherwise.
+    Prefer,
+    /// TLS with a verified server certificate, or no connection at all.
+    Require,
+}
+
+impl FromStr for SslMode {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "disable" => Ok(SslMode::Disable),
+            "allow" | "prefer" => Ok(SslMode::Prefer),
+            // The certificate is always verified, so libpq's stricter modes
+            // mean the same here.
+            "require" | "verify-ca" | "verify-full" => Ok(SslMode::Require),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown sslmode `{}`, expected disable, prefer or require",
+                other
+            ))),
+        }
+    }
+}
+
+impl SslMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            SslMode::Disable => "disable",
+            SslMode::Prefer => "prefer",
+            SslMode::Require => "require",
+        }
+    }
+}
+
+/// Size, timeouts, TLS and retry behaviour of the connection pool.
+#[derive(Debug, Clone)]
+pub struct PoolSettings {
+    /// Most connections open at the same time.
+    pub max_size: usize,
+    /// Connections the pool monitor opens ahead of demand; see
+    /// [`spawn_pool_monitor`] for why this is only approximate.
+    pub min_idle: usize,
+    /// How long a query waits for a free connection before giving up.
+    pub wait_timeout_ms: u64,
+    /// How long opening a new connection may take.
+    pub connect_timeout_ms: u64,
+    /// How long checking an idle connection before reuse may take.
+    pub recycle_timeout_ms: u64,
+    /// Connections are replaced once they are this old, which also drops the
+    /// prepared statements each one caches; `0` keeps them for good.
+    pub max_lifetime_secs: u64,
+    /// Overrides the `sslmode` of the database URL; without either,
+    /// connections are plain.
+    pub ssl_mode: Option<SslMode>,
+    /// PEM file of extra certificate authorities trusted besides the
+    /// bundled web roots, for servers with a private CA.
+    pub ssl_root_cert: Option<String>,
+    /// How often startup tries to reach the database before serving without it.
+    pub startup_attempts: u32,
+    /// Wait after the first failed attempt; it doubles after each one.
+    pub retry_backoff_ms: u64,
+    /// Longest wait between two attempts.
+    pub retry_backoff_max_ms: u64,
+    /// How often the pool monitor checks the database.
+    pub health_check_interval_secs: u64,
+}
+
+pub static POOL_SETTINGS: Lazy<PoolSettings> = Lazy::new(|| PoolSettings {
+    max_size: env_or("DATABASE_POOL_MAX_SIZE", 16),
+    min_idle: env_or("DATABASE_POOL_MIN_IDLE", 0),
+    wait_timeout_ms: env_or("DATABASE_POOL_WAIT_TIMEOUT_MS", 5_000),
+    connect_timeout_ms: env_or("DATABASE_CONNECT_TIMEOUT_MS", 5_000),
+    recycle_timeout_ms: env_or("DATABASE_RECYCLE_TIMEOUT_MS", 5_000),
+    max_lifetime_secs: env_or("DATABASE_CONNECTION_MAX_LIFETIME_SECS", 1_800),
+    // A typo here must not quietly turn TLS off.
+    ssl_mode: env::var("DATABASE_SSL_MODE")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse().unwrap_or_else(|e| panic!("Invalid DATABASE_SSL_MODE: {}", e))),
+    ssl_root_cert: env::var("DATABASE_SSL_ROOT_CERT").ok().filter(|v| !v.is_empty()),
+    startup_attempts: env_or("DATABASE_STARTUP_ATTEMPTS", 5),
+    retry_backoff_ms: env_or("DATABASE_RETRY_BACKOFF_MS", 500),
+    retry_backoff_max_ms: env_or("DATABASE_RETRY_BACKOFF_MAX_MS", 10_000),
+    health_check_interval_secs: env_or("DATABASE_HEALTH_CHECK_INTERVAL_SECS", 10),
+});
+
+impl PoolSettings {
+    /// Wait before attempt `attempt + 1`, counting the first attempt as 0.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self.retry_backoff_ms.saturating_mul(1u64 << attempt.min(32));
+        Duration::from_millis(delay.min(self.retry_backoff_max_ms))
+    }
+
+    /// The TLS mode for `database_url`: the setting, else the URL's own
+    /// `sslmode`, else plain connections as before TLS was configurable.
+    fn ssl_mode_for(&self, database_url: &str) -> Result<SslMode, AppError> {
+        if let Some(mode) = self.ssl_mode {
+            return Ok(mode);
+        }
+        let query = database_url.split_once('?').map_or("", |(_, query)| query);
+        match query.split('&').find_map(|pair| pair.strip_prefix("sslmode=")) {
+            Some(mode) => mode.parse(),
+            None => Ok(SslMode::Disable),
+        }
+    }
+
+    /// `database_url` with the TLS settings spelled out for libpq, which the
+    /// blocking connections of the migrations use. They replace any
+    /// `sslmode` or `sslrootcert` the URL already has.
+    pub fn libpq_url(&self, database_url: &str) -> String {
+        let mut overrides = Vec::new();
+        if let Some(mode) = self.ssl_mode {
+            overrides.push(("sslmode", mode.as_str().to_string()));
+        }
+        if let Some(root_cert) = &self.ssl_root_cert {
+            overrides.push(("sslrootcert", root_cert.clone()));
+        }
+        if overrides.is_empty() {
+            return database_url.to_string();
+        }
+        let (base, query) = database_url.split_once('?').unwrap_or((database_url, ""));
+        let kept = query.split('&').filter(|pair| {
+            let key = pair.split_once('=').map_or(*pair, |(key, _)| key);
+            !pair.is_empty() && !overrides.iter().any(|(name, _)| *name == key)
+        });
+        let params: Vec<String> = kept
+            .map(str::to_string)
+            .chain(overrides.iter().map(|(name, value)| format!("{}={}", name, value)))
+            .collect();
+        format!("{}?{}", base, params.join("&"))
+    }
+}
+
+fn tls_config(settings: &PoolSettings) -> Result<ClientConfig, AppError> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(path) = &settings.ssl_root_cert {
+        let certificates = CertificateDer::pem_file_iter(path)
+            .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| AppError::InternalError(format!("Failed to read {}: {}", path, e)))?;
+        for certificate in certificates {
+            roots
+                .add(certificate)
+                .map_err(|e| AppError::InternalError(format!("Invalid certificate in {}: {}", path, e)))?;
+        }
+    }
+    let config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| AppError::InternalError(format!("Failed to configure TLS: {}", e)))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(config)
+}
+
+/// Opens a connection the way `mode` asks for, verifying the server
+/// certificate whenever TLS is used.
+fn establish(url: &str, mode: SslMode, tls: MakeRustlsConnect) -> BoxFuture<'_, ConnectionResult<AsyncPgConnection>> {
+    async move {
+        let bad_connection = |e: tokio_postgres::Error| ConnectionError::BadConnection(e.to_string());
+        let mut config: tokio_postgres::Config = url.parse().map_err(bad_connection)?;
+        // Like libpq, TLS only applies to TCP connections.
+        let local = config.get_hosts().iter().all(|host| !matches!(host, Host::Tcp(_)));
+        if local || mode == SslMode::Disable {
+            config.ssl_mode(PgSslMode::Disable);
+            let (client, connection) = config.connect(NoTls).await.map_err(bad_connection)?;
+            return AsyncPgConnection::try_from_client_and_connection(client, connection).await;
+        }
+        config.ssl_mode(if mode == SslMode::Require { PgSslMode::Require } else { PgSslMode::Prefer });
+        let (client, connection) = config.connect(tls).await.map_err(bad_connection)?;
+        AsyncPgConnection::try_from_client_and_connection(client, connection).await
+    }
+    .boxed()
+}
+
+/// Builds an async pool for `database_url`; no connection is opened until
+/// one is asked for.
+pub fn build_pool(database_url: &str, settings: &PoolSettings) -> Result<DbPool, AppError> {
+    let mode = settings.ssl_mode_for(database_url)?;
+    let tls = MakeRustlsConnect::new(tls_config(settings)?);
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(move |url| establish(url, mode, tls.clone()));
+    let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new_with_config(database_url, config);
+    let max_lifetime = (settings.max_lifetime_secs > 0).then(|| Duration::from_secs(settings.max_lifetime_secs));
+    Pool::builder(manager)
+        .max_size(settings.max_size)
+        .wait_timeout(Some(Duration::from_millis(settings.wait_timeout_ms)))
+        .create_timeout(Some(Duration::from_millis(settings.connect_timeout_ms)))
+        .recycle_timeout(Some(Duration::from_millis(settings.recycle_timeout_ms)))
+        .runtime(Runtime::Tokio1)
+        .pre_recycle(Hook::sync_fn(move |_, metrics| match max_lifetime {
+            Some(max_lifetime) if metrics.age() > max_lifetime => {
+                Err(HookError::message("connection reached its maximum lifetime"))
+            }
+            _ => Ok(()),
+        }))
+        .build()
+        .map_err(|e| AppError::ServiceUnavailable(format!("Failed to create pool: {}", e)))
+}
+
+/// Hands out a connection and returns it straight away; fails with
+/// `ServiceUnavailable` while the database cannot be reached.
+pub async fn ping(pool: &DbPool) -> Result<(), AppError> {
+    pool.get().await.map(drop).map_err(AppError::from)
+}
+
 // Initialize the database pool
-pub fn init_pool() -> Result<DbPool, AppError> {
+pub async fn init_pool() -> Result<DbPool, AppError> {
     // Load the environment variables
     dotenv().ok();
 
     // Get the database URL from the environment
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let manager = ConnectionManager::<PgConnection>::new(&database_url);
+    let settings = &*POOL_SETTINGS;
+    let pool = build_pool(&database_url, settings)?;
+
+    // Give a database that is still starting some time before serving
+    // without it; the pool connects by itself once it is back.
+    for attempt in 0..settings.startup_attempts.max(1) {
+        if attempt > 0 {
+            let delay = settings.backoff(attempt - 1);
+            log::warn!("database unreachable, retrying in {:?}", delay);
+            actix_web::rt::time::sleep(delay).await;
+        }
+        if ping(&pool).await.is_ok() {
+            return Ok(pool);
+        }
+    }
+    log::error!("database unreachable, serving 503 until it is back");
+    Ok(pool)
+}
+
+/// Logs when the database goes away and when it comes back, checking one
+/// connection every `health_check_interval_secs`. Requests recover on their
+/// own, because broken connections fail their check before reuse and new ones
+/// are opened as needed.
+///
+/// deadpool has no minimum of idle connections: it opens them only when one
+/// is asked for. While the database is up the monitor tops the pool up to
+/// `min_idle` after each check, so connections dropped in between (for
+/// instance at their maximum lifetime) stay missing until the next one.
+pub(crate) fn spawn_pool_monitor(pool: DbPool) {
+    let settings = &*POOL_SETTINGS;
+    let period = Duration::from_secs(settings.health_check_interval_secs.max(1));
+    let min_idle = settings.min_idle.min(settings.max_size);
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(period);
+        let mut available = true;
+        loop {
+            interval.tick().await;
+            let reachable = match pool.get().await {
+                Ok(_) => true,
+                Err(PoolError::Backend(_) | PoolError::Timeout(TimeoutType::Create)) => false,
+                // Every connection being busy says nothing about the database.
+                Err(_) => continue,
+            };
+            match (available, reachable) {
+                (true, false) => log::error!("database unavailable"),
+                (false, true) => log::info!("database available again"),
+                _ => {}
+            }
+            available = reachable;
+            if reachable && pool.status().size < min_idle {
+                // Holding the connections together makes the pool open new
+                // ones rather than hand out the same idle connection again.
+                join_all((0..min_idle).map(|_| pool.get())).await;
+            }
+        }
+    });
+}
 
+/// Whether the server applies pending migrations itself when it starts
+/// (`RUN_MIGRATIONS=true`); otherwise they are run with `migrate up`.
+pub fn run_migrations_on_startup() -> bool {
+    env_or("RUN_MIGRATIONS", false)
+}
 
-    // Create the database pool with the connection managers.
-    let pool = r2d2::Pool::builder()
-    .build(manager)
-        .map_err(|e| AppError::ServiceUnavailable(format!("Failed to create pool: {}", e)))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn settings() -> PoolSettings {
+        PoolSettings {
+            max_size: 4,
+            min_idle: 0,
+            wait_timeout_ms: 100,
+            connect_timeout_ms: 100,
+            recycle_timeout_ms: 100,
+            max_lifetime_secs: 0,
+            ssl_mode: None,
+            ssl_root_cert: None,
+            startup_attempts: 1,
+            retry_backoff_ms: 500,
+            retry_backoff_max_ms: 3_000,
+            health_check_interval_secs: 1,
+        }
+    }
 
-    // Return the database pool
-    pool.get().map_err(|e| AppError::ServiceUnavailable(format!("Failed to get connection: {}", e)))?;
-    Ok(pool)
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let settings = settings();
+        let delays: Vec<u64> = (0..5).map(|attempt| settings.backoff(attempt).as_millis() as u64).collect();
+        assert_eq!(delays, vec![500, 1_000, 2_000, 3_000, 3_000]);
+        assert_eq!(settings.backoff(u32::MAX), Duration::from_millis(3_000));
+    }
+
+    #[test]
+    fn ssl_mode_setting_overrides_the_url() {
+        let mut settings = settings();
+        assert_eq!(settings.ssl_mode_for("postgres://db/app").unwrap(), SslMode::Disable);
+        assert_eq!(settings.ssl_mode_for("postgres://db/app?sslmode=require").unwrap(), SslMode::Require);
+        assert!(settings.ssl_mode_for("postgres://db/app?sslmode=sometimes").is_err());
+
+        settings.ssl_mode = Some(SslMode::Prefer);
+        assert_eq!(settings.ssl_mode_for("postgres://db/app?sslmode=require").unwrap(), SslMode::Prefer);
+        settings.ssl_root_cert = Some("/etc/ca.pem".to_string());
+        assert_eq!(
+            settings.libpq_url("postgres://db/app?sslmode=require"),
+            "postgres://db/app?sslmode=prefer&sslrootcert=/etc/ca.pem"
+        );
+        assert_eq!(
+            settings.libpq_url("postgres://db/app?application_name=zuzu&sslmode=require&sslrootcert=/old.pem"),
+            "postgres://db/app?application_name=zuzu&sslmode=prefer&sslrootcert=/etc/ca.pem"
+        );
+        assert_eq!(settings.libpq_url("postgres://db/app"), "postgres://db/app?sslmode=prefer&sslrootcert=/etc/ca.pem");
+    }
 
+    /// A pool for a database that is not there still builds, and hands out
+    /// `ServiceUnavailable` rather than failing the server.
+    #[actix_web::test]
+    async fn unreachable_database_is_service_unavailable() {
+        let pool = build_pool("postgres://nobody@127.0.0.1:1/none", &settings()).unwrap();
+        assert!(matches!(ping(&pool).await, Err(AppError::ServiceUnavailable(_))));
+    }
 }
@@ -11,5 +11,9 @@ pub fn init_error_handlers() -> ErrorHandlers<BoxBody> {
         .handler(StatusCode::UNPROCESSABLE_ENTITY, error_handler)
         .handler(StatusCode::UNAUTHORIZED, error_handler)
         .handler(StatusCode::FORBIDDEN, error_handler)
+        .handler(StatusCode::CONFLICT, error_handler)
+        .handler(StatusCode::PRECONDITION_FAILED, error_handler)
+        .handler(StatusCode::PRECONDITION_REQUIRED, error_handler)
+        .handler(StatusCode::TOO_MANY_REQUESTS, error_handler)
         .handler(StatusCode::SERVICE_UNAVAILABLE, error_handler)
 }
\ No newline at end of file
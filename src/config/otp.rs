@@ -0,0 +1,80 @@
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use crate::config::env_or;
+use crate::domain::models::user::TwoFactorMethodEnum;
+use crate::infrastructure::external::email::EmailOtpSender;
+use crate::infrastructure::external::file_sender::FileOtpSender;
+use crate::infrastructure::external::http_provider::JsonHttpProvider;
+use crate::infrastructure::external::otp_sender::OtpSenders;
+use crate::infrastructure::external::sms::SmsOtpSender;
+use crate::infrastructure::external::whatsapp::WhatsappOtpSender;
+
+/// Tunables for one-time passwords.
+#[derive(Debug, Clone)]
+pub struct OtpSettings {
+    pub length: usize,
+    pub ttl_minutes: i64,
+    pub max_attempts: i32,
+}
+
+pub static OTP_SETTINGS: Lazy<OtpSettings> = Lazy::new(|| OtpSettings {
+    length: env_or("OTP_LENGTH", 6),
+    ttl_minutes: env_or("OTP_TTL_MINUTES", 5),
+    max_attempts: env_or("OTP_MAX_ATTEMPTS", 5),
+});
+
+// Initialize the OTP senders.
+//
+// `OTP_DELIVERY=live` wires the SMTP and HTTP provider senders for every channel
+// that has its settings present; anything else (the default) routes all channels
+// to the file/log sender so local development never contacts real providers.
+pub fn init_otp_senders() -> OtpSenders {
+    let delivery = env::var("OTP_DELIVERY").unwrap_or_else(|_| "file".to_string());
+    if delivery != "live" {
+        let path = env::var("OTP_OUTBOX_PATH").ok().map(PathBuf::from);
+        return [TwoFactorMethodEnum::Email, TwoFactorMethodEnum::Sms, TwoFactorMethodEnum::Whatsapp]
+            .into_iter()
+            .fold(OtpSenders::new(), |senders, method| {
+                senders.register(method, Arc::new(FileOtpSender::new(method, path.clone())))
+            });
+    }
+
+    let mut senders = OtpSenders::new();
+
+    if let (Ok(host), Ok(from)) = (env::var("SMTP_HOST"), env::var("SMTP_FROM")) {
+        match EmailOtpSender::new(
+            &host,
+            env_or("SMTP_PORT", 587),
+            env::var("SMTP_USERNAME").unwrap_or_default(),
+            env::var("SMTP_PASSWORD").unwrap_or_default(),
+            &from,
+        ) {
+            Ok(sender) => senders = senders.register(TwoFactorMethodEnum::Email, Arc::new(sender)),
+            Err(e) => log::warn!("Email OTP delivery disabled: {}", e),
+        }
+    }
+
+    if let Ok(endpoint) = env::var("SMS_PROVIDER_URL") {
+        let provider = JsonHttpProvider::new(
+            endpoint,
+            env::var("SMS_PROVIDER_TOKEN").unwrap_or_default(),
+            env::var("SMS_SENDER_ID").unwrap_or_default(),
+            "sms",
+        );
+        senders = senders.register(TwoFactorMethodEnum::Sms, Arc::new(SmsOtpSender::new(Arc::new(provider))));
+    }
+
+    if let Ok(endpoint) = env::var("WHATSAPP_PROVIDER_URL") {
+        let provider = JsonHttpProvider::new(
+            endpoint,
+            env::var("WHATSAPP_PROVIDER_TOKEN").unwrap_or_default(),
+            env::var("WHATSAPP_SENDER_ID").unwrap_or_default(),
+            "whatsapp",
+        );
+        senders = senders.register(TwoFactorMethodEnum::Whatsapp, Arc::new(WhatsappOtpSender::new(Arc::new(provider))));
+    }
+
+    senders
+}
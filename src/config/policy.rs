@@ -0,0 +1,20 @@
+use std::env;
+use std::fs;
+use crate::domain::models::policy::PolicySet;
+use crate::domain::services::policy_services::PolicyEngine;
+
+const DEFAULT_POLICIES: &str = include_str!("../../policies.json");
+
+/// Loads the policies from `POLICY_FILE` (default `policies.json`). When the
+/// file cannot be read the bundled defaults are used instead; a file that is
+/// present but invalid stops the server rather than silently allowing less.
+pub fn init_policy_engine() -> PolicyEngine {
+    let path = env::var("POLICY_FILE").unwrap_or_else(|_| "policies.json".to_string());
+    let source = fs::read_to_string(&path).unwrap_or_else(|e| {
+        log::warn!("Could not read policy file {}: {}; using bundled policies", path, e);
+        DEFAULT_POLICIES.to_string()
+    });
+    let policies: PolicySet = serde_json::from_str(&source)
+        .unwrap_or_else(|e| panic!("Invalid policy file {}: {}", path, e));
+    PolicyEngine::new(policies)
+}
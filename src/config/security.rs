@@ -0,0 +1,54 @@
+use std::env;
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use crate::config::env_or;
+use crate::utils::rate_limit::RateLimiter;
+
+/// Brute-force protection shared by every way of signing in.
+#[derive(Debug, Clone)]
+pub struct LoginSettings {
+    /// Consecutive failures before the account is temporarily locked.
+    pub max_failed_attempts: i32,
+    pub lockout_minutes: i64,
+    /// Sign-in requests allowed per client address and per identifier each minute.
+    pub rate_limit_per_minute: u32,
+}
+
+pub static LOGIN_SETTINGS: Lazy<LoginSettings> = Lazy::new(|| LoginSettings {
+    max_failed_attempts: env_or("LOGIN_MAX_FAILED_ATTEMPTS", 5),
+    lockout_minutes: env_or("LOGIN_LOCKOUT_MINUTES", 15),
+    rate_limit_per_minute: env_or("LOGIN_RATE_LIMIT_PER_MINUTE", 10),
+});
+
+#[derive(Debug, Clone)]
+pub struct MagicLinkSettings {
+    pub ttl_minutes: i64,
+    /// Page that receives `?token=...` and posts it to the consume endpoint.
+    pub base_url: String,
+}
+
+pub static MAGIC_LINK_SETTINGS: Lazy<MagicLinkSettings> = Lazy::new(|| MagicLinkSettings {
+    ttl_minutes: env_or("MAGIC_LINK_TTL_MINUTES", 10),
+    base_url: env::var("MAGIC_LINK_BASE_URL")
+        .unwrap_or_else(|_| "http://localhost:8082/magic-link".to_string()),
+});
+
+pub fn init_login_rate_limiter() -> RateLimiter {
+    RateLimiter::new(LOGIN_SETTINGS.rate_limit_per_minute, Duration::from_secs(60))
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthorizationSettings {
+    /// Put role names and permissions into access tokens so checks (ours and
+    /// downstream services') need no database round trip. Embedded grants stay
+    /// valid until the token expires, even if roles change meanwhile, which is
+    /// why it is off unless `TOKEN_EMBED_PERMISSIONS=true`.
+    pub embed_in_tokens: bool,
+    /// How long resolved permissions are cached per account.
+    pub cache_ttl_seconds: u64,
+}
+
+pub static AUTHORIZATION_SETTINGS: Lazy<AuthorizationSettings> = Lazy::new(|| AuthorizationSettings {
+    embed_in_tokens: env_or("TOKEN_EMBED_PERMISSIONS", false),
+    cache_ttl_seconds: env_or("PERMISSION_CACHE_TTL_SECONDS", 60),
+});
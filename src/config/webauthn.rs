@@ -0,0 +1,24 @@
+use std::env;
+use once_cell::sync::Lazy;
+use crate::config::env_or;
+
+/// Relying party settings for WebAuthn ceremonies.
+#[derive(Debug, Clone)]
+pub struct WebauthnSettings {
+    /// Effective domain the credentials are scoped to, e.g. `example.com`.
+    pub rp_id: String,
+    pub rp_name: String,
+    /// Exact origin browsers report in client data, e.g. `https://app.example.com`.
+    pub origin: String,
+    pub timeout_seconds: i64,
+    /// How recent a sign in must be to register a security key.
+    pub reauthentication_seconds: i64,
+}
+
+pub static WEBAUTHN_SETTINGS: Lazy<WebauthnSettings> = Lazy::new(|| WebauthnSettings {
+    rp_id: env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string()),
+    rp_name: env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "zuzu".to_string()),
+    origin: env::var("WEBAUTHN_ORIGIN").unwrap_or_else(|_| "http://localhost:8082".to_string()),
+    timeout_seconds: env_or("WEBAUTHN_TIMEOUT_SECONDS", 300),
+    reauthentication_seconds: env_or("WEBAUTHN_REAUTHENTICATION_SECONDS", 300),
+});
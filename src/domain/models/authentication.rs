@@ -1,7 +1,51 @@
+use crate::infrastructure::database::schemas::schemas::magic_links;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginRequest {
-    pub username: String,
+    /// Username or email address, matched case-insensitively.
+    #[serde(alias = "username", alias = "email")]
+    pub identifier: String,
     pub password: String,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MagicLinkConsumeRequest {
+    pub token: String,
+    /// Falls back to the `magic_link_nonce` cookie set when the link was requested.
+    pub device_nonce: Option<String>,
+}
+
+/// Handed to the device that requested a magic link; only that device can redeem it.
+#[derive(Debug, Clone, Serialize)]
+pub struct MagicLinkIssued {
+    pub device_nonce: String,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Selectable, Queryable, Identifiable, Clone)]
+#[diesel(table_name = magic_links)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MagicLink {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub nonce_hash: Vec<u8>,
+    pub expires_at: chrono::NaiveDateTime,
+    pub consumed_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = magic_links)]
+pub struct NewMagicLink {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub nonce_hash: Vec<u8>,
+    pub expires_at: chrono::NaiveDateTime,
+}
@@ -1,2 +1,6 @@
 pub mod user;
-pub mod authentication;
\ No newline at end of file
+pub mod authentication;
+pub mod otp;
+pub mod webauthn;
+pub mod rbac;
+pub mod policy;
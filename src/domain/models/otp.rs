@@ -0,0 +1,71 @@
+use crate::domain::models::user::TwoFactorMethodEnum;
+use crate::infrastructure::database::schemas::schemas::otp_codes;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+#[derive(Debug, Selectable, Queryable, Identifiable, Clone)]
+#[diesel(table_name = otp_codes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OtpCode {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub method: TwoFactorMethodEnum,
+    pub purpose: String,
+    pub recipient: String,
+    pub code_hash: Vec<u8>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub expires_at: chrono::NaiveDateTime,
+    pub consumed_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = otp_codes)]
+pub struct NewOtpCode {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub method: TwoFactorMethodEnum,
+    pub purpose: String,
+    pub recipient: String,
+    pub code_hash: Vec<u8>,
+    pub max_attempts: i32,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+/// What a one-time password was issued for. A code issued for one purpose
+/// can never be redeemed for another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpPurpose {
+    Login,
+    VerifyPhone,
+    MagicLink,
+}
+
+impl OtpPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OtpPurpose::Login => "login",
+            OtpPurpose::VerifyPhone => "verify_phone",
+            OtpPurpose::MagicLink => "magic_link",
+        }
+    }
+
+    /// Human readable name used in delivered messages.
+    pub fn label(&self) -> &'static str {
+        match self {
+            OtpPurpose::Login => "login",
+            OtpPurpose::VerifyPhone => "phone",
+            OtpPurpose::MagicLink => "sign-in",
+        }
+    }
+}
+
+/// Returned to the client instead of tokens when a second factor is required.
+#[derive(Debug, Clone, Serialize)]
+pub struct OtpChallenge {
+    pub mfa_required: bool,
+    pub challenge_id: uuid::Uuid,
+    pub method: TwoFactorMethodEnum,
+    pub expires_at: chrono::NaiveDateTime,
+}
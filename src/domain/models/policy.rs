@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use crate::domain::models::rbac::{EffectivePermission, PermissionAction};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+/// A value in a condition: either an attribute path into the request such as
+/// `{"attr": "subject.id"}`, or any other JSON value taken literally.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Operand {
+    Attribute { attr: String },
+    Literal(serde_json::Value),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+    Equals(Operand, Operand),
+    /// The first operand is an element of the second, which must be an array.
+    In(Operand, Operand),
+    HasRole(String),
+    HasPermission {
+        permission: String,
+        action: PermissionAction,
+    },
+}
+
+/// One declarative rule. It applies when the action and resource type match
+/// (`*` matches anything) and its condition, if any, holds.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Policy {
+    pub id: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub effect: Effect,
+    pub actions: Vec<String>,
+    pub resources: Vec<String>,
+    #[serde(default)]
+    pub condition: Option<Condition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PolicySet {
+    pub policies: Vec<Policy>,
+}
+
+/// Who is asking: the account, its (inherited) roles and permissions, and
+/// its account record as free-form attributes.
+#[derive(Debug, Serialize, Clone)]
+pub struct PolicySubject {
+    pub id: uuid::Uuid,
+    pub roles: Vec<String>,
+    pub permissions: Vec<EffectivePermission>,
+    pub attributes: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolicyResource {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub attributes: serde_json::Value,
+}
+
+impl PolicyResource {
+    /// An account; its record is looked up when the policies are evaluated.
+    pub fn account(id: uuid::Uuid) -> Self {
+        PolicyResource { kind: "account".to_string(), id: Some(id.to_string()), attributes: serde_json::Value::Null }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PolicyRequest {
+    pub subject: PolicySubject,
+    pub action: String,
+    pub resource: PolicyResource,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PolicyTrace {
+    pub policy: String,
+    pub effect: Effect,
+    /// Action and resource type matched.
+    pub applicable: bool,
+    /// Applicable and the condition held.
+    pub matched: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PolicyDecision {
+    pub allowed: bool,
+    /// Policy that decided the outcome; `None` when nothing matched.
+    pub policy: Option<String>,
+    pub reason: String,
+    pub trace: Vec<PolicyTrace>,
+}
@@ -0,0 +1,226 @@
+use crate::infrastructure::database::schemas::schemas::{
+    account_roles, permissions, role_parents, role_permissions, roles,
+};
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Selectable, Queryable, Identifiable, Serialize, Clone)]
+#[diesel(table_name = roles)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Role {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable, AsChangeset, Clone)]
+#[diesel(table_name = roles)]
+pub struct NewRole {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Selectable, Queryable, Identifiable, Serialize, Clone)]
+#[diesel(table_name = permissions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Permission {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable, AsChangeset, Clone)]
+#[diesel(table_name = permissions)]
+pub struct NewPermission {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Grants a role the flagged actions on one permission (resource).
+#[derive(Debug, Selectable, Queryable, Identifiable, Serialize, Clone)]
+#[diesel(table_name = role_permissions)]
+#[diesel(primary_key(role_id, permission_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RolePermission {
+    pub role_id: uuid::Uuid,
+    pub permission_id: uuid::Uuid,
+    pub read: bool,
+    pub write: bool,
+    pub update: bool,
+    pub delete: bool,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable, Clone)]
+#[diesel(table_name = role_permissions)]
+pub struct NewRolePermission {
+    pub role_id: uuid::Uuid,
+    pub permission_id: uuid::Uuid,
+    pub read: bool,
+    pub write: bool,
+    pub update: bool,
+    pub delete: bool,
+}
+
+/// `role_id` inherits every grant of `parent_id`.
+#[derive(Debug, Selectable, Queryable, Identifiable, Serialize, Clone)]
+#[diesel(table_name = role_parents)]
+#[diesel(primary_key(role_id, parent_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RoleParent {
+    pub role_id: uuid::Uuid,
+    pub parent_id: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = role_parents)]
+pub struct NewRoleParent {
+    pub role_id: uuid::Uuid,
+    pub parent_id: uuid::Uuid,
+}
+
+#[derive(Debug, Selectable, Queryable, Identifiable, Serialize, Clone)]
+#[diesel(table_name = account_roles)]
+#[diesel(primary_key(account_id, role_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountRole {
+    pub account_id: uuid::Uuid,
+    pub role_id: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = account_roles)]
+pub struct NewAccountRole {
+    pub account_id: uuid::Uuid,
+    pub role_id: uuid::Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionAction {
+    Read,
+    Write,
+    Update,
+    Delete,
+}
+
+impl PermissionAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PermissionAction::Read => "read",
+            PermissionAction::Write => "write",
+            PermissionAction::Update => "update",
+            PermissionAction::Delete => "delete",
+        }
+    }
+}
+
+/// What an account may do on one permission, merged across all of its roles.
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct EffectivePermission {
+    pub name: String,
+    pub read: bool,
+    pub write: bool,
+    pub update: bool,
+    pub delete: bool,
+}
+
+impl EffectivePermission {
+    pub fn from_grant(name: &str, grant: &RolePermission) -> Self {
+        let mut permission = EffectivePermission { name: name.to_string(), ..Default::default() };
+        permission.merge(grant);
+        permission
+    }
+
+    pub fn allows(&self, action: PermissionAction) -> bool {
+        match action {
+            PermissionAction::Read => self.read,
+            PermissionAction::Write => self.write,
+            PermissionAction::Update => self.update,
+            PermissionAction::Delete => self.delete,
+        }
+    }
+
+    /// Token form: the permission name and the letters of the allowed actions,
+    /// e.g. `users:ru` for read and update.
+    pub fn to_compact(&self) -> String {
+        let flags = [(self.read, 'r'), (self.write, 'w'), (self.update, 'u'), (self.delete, 'd')];
+        let actions: String = flags.iter().filter(|(on, _)| *on).map(|(_, c)| *c).collect();
+        format!("{}:{}", self.name, actions)
+    }
+
+    pub fn from_compact(value: &str) -> Option<Self> {
+        let (name, actions) = value.rsplit_once(':')?;
+        if name.is_empty() || !actions.chars().all(|c| "rwud".contains(c)) {
+            return None;
+        }
+        Some(EffectivePermission {
+            name: name.to_string(),
+            read: actions.contains('r'),
+            write: actions.contains('w'),
+            update: actions.contains('u'),
+            delete: actions.contains('d'),
+        })
+    }
+
+    /// A role grant adds to whatever other roles already allow.
+    pub fn merge(&mut self, grant: &RolePermission) {
+        self.read |= grant.read;
+        self.write |= grant.write;
+        self.update |= grant.update;
+        self.delete |= grant.delete;
+    }
+}
+
+/// An account's resolved authorization: its role names (inherited ones
+/// included) and merged permissions.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct AccessGrants {
+    pub roles: Vec<String>,
+    pub permissions: Vec<EffectivePermission>,
+}
+
+impl AccessGrants {
+    pub fn allows(&self, permission: &str, action: PermissionAction) -> bool {
+        self.permissions
+            .iter()
+            .any(|p| p.name == permission && p.allows(action))
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RoleWithPermissions {
+    #[serde(flatten)]
+    pub role: Role,
+    pub parents: Vec<uuid::Uuid>,
+    /// Grants attached to this role directly.
+    pub permissions: Vec<EffectivePermission>,
+    /// Grants including everything inherited from parent roles.
+    pub effective_permissions: Vec<EffectivePermission>,
+}
+
+/// One way an account reaches a grant: the role chain from a role assigned to
+/// the account up to the role holding the grant.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct GrantPath {
+    pub roles: Vec<String>,
+}
+
+/// Why an account has, or lacks, an action on a permission.
+#[derive(Debug, Serialize, Clone)]
+pub struct PermissionExplanation {
+    pub account_id: uuid::Uuid,
+    pub permission: String,
+    pub action: PermissionAction,
+    pub allowed: bool,
+    /// Roles assigned to the account directly.
+    pub assigned_roles: Vec<String>,
+    /// Every inheritance path ending at a role that grants the action.
+    pub paths: Vec<GrantPath>,
+}
@@ -1,5 +1,5 @@
 
-use crate::infrastructure::database::schemas::schemas::{password_hashes, accounts};
+use crate::infrastructure::database::schemas::schemas::{account_status_history, password_hashes, accounts};
 use crate::infrastructure::database::schemas::{ schemas::sql_types };
 use diesel::{Queryable, Identifiable, Insertable, Selectable, AsChangeset};
 use diesel_derive_enum::DbEnum;
@@ -22,6 +22,13 @@ pub struct User {
     pub login_attempts: i32,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub phone_verified: bool,
+    pub locked_until: Option<chrono::NaiveDateTime>,
+    /// Set while the account is soft deleted.
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    /// Incremented by every edit of the account (not by sign in bookkeeping);
+    /// the account's ETag.
+    pub version: i32,
 }
 
 #[derive(Debug, Deserialize, Insertable, Clone,AsChangeset)]
@@ -29,17 +36,41 @@ pub struct User {
 pub struct NewUser {
     pub username: String,
     pub email: String,
-    pub phone_number: String,
+    pub phone_number: Option<String>,
     pub is_active: bool,
     pub is_verified: bool,
     pub registration_date: chrono::NaiveDateTime,
-    pub last_login: chrono::NaiveDateTime,
+    pub last_login: Option<chrono::NaiveDateTime>,
     pub two_factor_method: TwoFactorMethodEnum,
     pub preferred_language: Option<String>,
     pub status: AccountStatusEnum,
 }
 
-#[derive(Debug,  Serialize, Deserialize, DbEnum, Clone)]
+/// Column changes to an account; `None` leaves a column as it is, and
+/// `Some(None)` clears a nullable one.
+#[derive(Debug, Default, Clone, AsChangeset)]
+#[diesel(table_name = accounts)]
+pub struct UserChangeset {
+    pub username: Option<String>,
+    pub email: Option<String>,
+    pub phone_number: Option<Option<String>>,
+    pub phone_verified: Option<bool>,
+    pub is_active: Option<bool>,
+    pub is_verified: Option<bool>,
+    pub two_factor_method: Option<TwoFactorMethodEnum>,
+    pub preferred_language: Option<String>,
+    pub status: Option<AccountStatusEnum>,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+impl UserChangeset {
+    /// Whether fields only account administrators may change are touched.
+    pub fn changes_managed_fields(&self) -> bool {
+        self.is_active.is_some() || self.is_verified.is_some() || self.status.is_some()
+    }
+}
+
+#[derive(Debug,  Serialize, Deserialize, DbEnum, Clone, Copy, PartialEq, Eq, Hash)]
 #[ExistingTypePath = "sql_types::TwoFactorMethodEnum"]
 pub enum TwoFactorMethodEnum {
     None,
@@ -47,10 +78,23 @@ pub enum TwoFactorMethodEnum {
     Whatsapp,
     Totp,
     Sms,
+    Webauthn,
+}
+
+impl TwoFactorMethodEnum {
+    /// Methods whose second factor is a one-time password delivered out of band.
+    pub fn is_otp_delivery(&self) -> bool {
+        matches!(self, TwoFactorMethodEnum::Email | TwoFactorMethodEnum::Whatsapp | TwoFactorMethodEnum::Sms)
+    }
+
+    /// Methods that deliver codes to the account's phone number.
+    pub fn uses_phone(&self) -> bool {
+        matches!(self, TwoFactorMethodEnum::Whatsapp | TwoFactorMethodEnum::Sms)
+    }
 }
 
 
-#[derive(Debug,  Serialize, Deserialize, DbEnum, Clone)]
+#[derive(Debug,  Serialize, Deserialize, DbEnum, Clone, Copy, PartialEq, Eq)]
 #[ExistingTypePath = "sql_types::AccountStatusEnum"]
 pub enum AccountStatusEnum {
     Active,
@@ -59,8 +103,140 @@ pub enum AccountStatusEnum {
     Deleted,
 }
 
+/// Administrative status changes, named as they appear in the URL.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusAction {
+    Suspend,
+    Lock,
+    Unlock,
+    Reactivate,
+}
 
-#[derive(Debug, Deserialize, Selectable, Queryable, Identifiable, Serialize, Clone)]
+impl StatusAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusAction::Suspend => "suspend",
+            StatusAction::Lock => "lock",
+            StatusAction::Unlock => "unlock",
+            StatusAction::Reactivate => "reactivate",
+        }
+    }
+}
+
+impl AccountStatusEnum {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatusEnum::Active => "active",
+            AccountStatusEnum::Locked => "locked",
+            AccountStatusEnum::Suspended => "suspended",
+            AccountStatusEnum::Deleted => "deleted",
+        }
+    }
+
+    /// The status `action` leads to from this one, or `None` when it does not
+    /// apply. Deleted accounts only come back through a restore.
+    pub fn apply(self, action: StatusAction) -> Option<AccountStatusEnum> {
+        use AccountStatusEnum::*;
+        match (self, action) {
+            (Active | Locked, StatusAction::Suspend) => Some(Suspended),
+            (Active, StatusAction::Lock) => Some(Locked),
+            (Locked, StatusAction::Unlock) => Some(Active),
+            (Suspended, StatusAction::Reactivate) => Some(Active),
+            _ => None,
+        }
+    }
+}
+
+/// One entry of an account's status history.
+#[derive(Debug, Serialize, Selectable, Queryable, Identifiable, Clone)]
+#[diesel(table_name = account_status_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct StatusChange {
+    pub id: uuid::Uuid,
+    pub account_id: uuid::Uuid,
+    pub from_status: AccountStatusEnum,
+    pub to_status: AccountStatusEnum,
+    pub reason: String,
+    /// The administrator behind the change; `None` for changes the system made.
+    pub changed_by: Option<uuid::Uuid>,
+    pub changed_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = account_status_history)]
+pub struct NewStatusChange {
+    pub account_id: uuid::Uuid,
+    pub from_status: AccountStatusEnum,
+    pub to_status: AccountStatusEnum,
+    pub reason: String,
+    pub changed_by: Option<uuid::Uuid>,
+}
+
+/// Narrows an account listing; unset fields do not filter.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct UserFilter {
+    /// Matched against username and email.
+    pub search: Option<String>,
+    pub status: Option<AccountStatusEnum>,
+    pub is_active: Option<bool>,
+    pub is_verified: Option<bool>,
+    pub two_factor_method: Option<TwoFactorMethodEnum>,
+    /// Registered at or after this moment.
+    pub registered_from: Option<chrono::NaiveDateTime>,
+    /// Registered strictly before this moment.
+    pub registered_before: Option<chrono::NaiveDateTime>,
+}
+
+/// Columns an account listing may be sorted by.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortField {
+    Username,
+    Email,
+    #[default]
+    RegistrationDate,
+    LastLogin,
+    CreatedAt,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeysetDirection {
+    /// Rows following the position, in listing order.
+    After,
+    /// Rows preceding the position.
+    Before,
+}
+
+/// A position in an account listing ordered by `(created_at, id)`; clients
+/// only ever see it signed, see `utils::cursor`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct AccountCursor {
+    #[serde(rename = "c")]
+    pub created_at: chrono::NaiveDateTime,
+    #[serde(rename = "i")]
+    pub id: uuid::Uuid,
+    #[serde(rename = "o")]
+    pub order: SortOrder,
+    #[serde(rename = "d")]
+    pub direction: KeysetDirection,
+    /// Fingerprint of the filter the listing was made with.
+    #[serde(rename = "f")]
+    pub filter: u64,
+}
+
+
+/// Never serialized, so it cannot end up in a response by accident.
+#[derive(Debug, Selectable, Queryable, Identifiable, Clone)]
 #[diesel(table_name = password_hashes)]
 #[diesel(belongs_to(User))]
 pub struct PasswordHash{
@@ -76,7 +252,7 @@ pub struct PasswordHash{
     pub updated_at: Option<chrono::NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize, Insertable)]
+#[derive(Debug, Insertable)]
 #[diesel(table_name = password_hashes)]
 pub struct NewPasswordHash {
     pub user_id: uuid::Uuid,
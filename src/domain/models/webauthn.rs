@@ -0,0 +1,129 @@
+use crate::infrastructure::database::schemas::schemas::{webauthn_challenges, webauthn_credentials};
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+#[derive(Debug, Selectable, Queryable, Identifiable, Serialize, Clone)]
+#[diesel(table_name = webauthn_credentials)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebauthnCredential {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    #[serde(skip_serializing)]
+    pub credential_id: Vec<u8>,
+    #[serde(skip_serializing)]
+    pub public_key: Vec<u8>,
+    pub sign_count: i64,
+    pub transports: serde_json::Value,
+    pub name: Option<String>,
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = webauthn_credentials)]
+pub struct NewWebauthnCredential {
+    pub user_id: uuid::Uuid,
+    pub credential_id: Vec<u8>,
+    pub public_key: Vec<u8>,
+    pub sign_count: i64,
+    pub transports: serde_json::Value,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Selectable, Queryable, Identifiable, Clone)]
+#[diesel(table_name = webauthn_challenges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebauthnChallenge {
+    pub id: uuid::Uuid,
+    pub user_id: Option<uuid::Uuid>,
+    pub challenge: Vec<u8>,
+    pub ceremony: String,
+    /// Whether a password was checked before this ceremony started.
+    pub after_password: bool,
+    pub expires_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = webauthn_challenges)]
+pub struct NewWebauthnChallenge {
+    pub user_id: Option<uuid::Uuid>,
+    pub challenge: Vec<u8>,
+    pub ceremony: String,
+    pub after_password: bool,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+pub const CEREMONY_REGISTRATION: &str = "registration";
+pub const CEREMONY_AUTHENTICATION: &str = "authentication";
+
+// The structures below mirror the WebAuthn JSON options passed to
+// `navigator.credentials.create()` / `.get()`, with binary fields base64url encoded.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntity {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialParameter {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub alg: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection {
+    pub resident_key: &'static str,
+    pub user_verification: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreationOptions {
+    pub rp: RelyingParty,
+    pub user: UserEntity,
+    pub challenge: String,
+    pub pub_key_cred_params: Vec<CredentialParameter>,
+    pub timeout: u64,
+    pub attestation: &'static str,
+    pub exclude_credentials: Vec<CredentialDescriptor>,
+    pub authenticator_selection: AuthenticatorSelection,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions {
+    pub challenge: String,
+    pub rp_id: String,
+    pub timeout: u64,
+    pub allow_credentials: Vec<CredentialDescriptor>,
+    pub user_verification: &'static str,
+}
+
+/// A ceremony started by the server; the client echoes `ceremony_id` back when finishing.
+#[derive(Debug, Clone, Serialize)]
+pub struct Ceremony<T> {
+    pub ceremony_id: uuid::Uuid,
+    #[serde(rename = "publicKey")]
+    pub public_key: T,
+}
@@ -0,0 +1,52 @@
+use actix_web::{web, Error};
+use chrono::Utc;
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::domain::models::authentication::{MagicLink, NewMagicLink};
+use crate::infrastructure::database::schemas::schemas::magic_links::dsl;
+use crate::infrastructure::database::schemas::schemas::magic_links::dsl::magic_links;
+use crate::utils::errors::AppError;
+
+pub struct MagicLinkRepository {
+    pool: web::Data<DbPool>,
+}
+
+impl MagicLinkRepository {
+    pub fn new(pool: web::Data<DbPool>) -> Self {
+        MagicLinkRepository { pool }
+    }
+
+    pub async fn create(&self, data: NewMagicLink) -> Result<MagicLink, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let link = diesel::insert_into(magic_links)
+            .values(&data)
+            .get_result::<MagicLink>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(link)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<MagicLink, Error> {
+        let query = magic_links.filter(dsl::id.eq(id));
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let link = query
+            .first::<MagicLink>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(link)
+    }
+
+    /// Marks a link as used. Returns `false` if it had already been consumed.
+    pub async fn consume(&self, id: Uuid) -> Result<bool, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let updated = diesel::update(magic_links.filter(dsl::id.eq(id)).filter(dsl::consumed_at.is_null()))
+            .set(dsl::consumed_at.eq(Utc::now().naive_utc()))
+            .execute(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(updated == 1)
+    }
+}
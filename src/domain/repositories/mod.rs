@@ -1 +1,6 @@
-pub(crate) mod user_repository;
\ No newline at end of file
+pub(crate) mod user_repository;
+pub(crate) mod otp_repository;
+pub(crate) mod webauthn_repository;
+pub(crate) mod magic_link_repository;
+pub(crate) mod role_repository;
+pub(crate) mod permission_repository;
@@ -0,0 +1,91 @@
+use actix_web::{web, Error};
+use chrono::Utc;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::RunQueryDsl;
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::domain::models::otp::{NewOtpCode, OtpCode};
+use crate::infrastructure::database::schemas::schemas::otp_codes::dsl;
+use crate::infrastructure::database::schemas::schemas::otp_codes::dsl::otp_codes;
+use crate::utils::errors::AppError;
+
+pub struct OtpRepository {
+    pool: web::Data<DbPool>,
+}
+
+impl OtpRepository {
+    pub fn new(pool: web::Data<DbPool>) -> Self {
+        OtpRepository { pool }
+    }
+
+    pub async fn create(&self, data: NewOtpCode) -> Result<OtpCode, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let code = diesel::insert_into(otp_codes)
+            .values(&data)
+            .get_result::<OtpCode>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(code)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<OtpCode, Error> {
+        let query = otp_codes.filter(dsl::id.eq(id));
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let code = query
+            .first::<OtpCode>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(code)
+    }
+
+    /// Counts one verification attempt. Returns `None` once the code has used up
+    /// its attempts, so concurrent guesses cannot exceed the limit.
+    pub async fn register_attempt(&self, id: Uuid) -> Result<Option<OtpCode>, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let code = diesel::update(
+            otp_codes
+                .filter(dsl::id.eq(id))
+                .filter(dsl::attempts.lt(dsl::max_attempts)),
+        )
+        .set(dsl::attempts.eq(dsl::attempts + 1))
+        .get_result::<OtpCode>(&mut conn)
+        .await
+        .optional()
+        .map_err(AppError::from)?;
+        Ok(code)
+    }
+
+    /// Marks a code as used. Returns `false` if it had already been consumed.
+    pub async fn consume(&self, id: Uuid) -> Result<bool, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let updated = diesel::update(
+            otp_codes
+                .filter(dsl::id.eq(id))
+                .filter(dsl::consumed_at.is_null()),
+        )
+        .set(dsl::consumed_at.eq(Utc::now().naive_utc()))
+        .execute(&mut conn)
+        .await
+        .map_err(AppError::from)?;
+        Ok(updated == 1)
+    }
+
+    /// Consumes every outstanding code of a user for the given purpose, so only
+    /// the most recently issued one can be redeemed.
+    pub async fn invalidate_pending(&self, user_id: Uuid, purpose: &str) -> Result<(), Error> {
+        let purpose = purpose.to_string();
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        diesel::update(
+            otp_codes
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::purpose.eq(purpose))
+                .filter(dsl::consumed_at.is_null()),
+        )
+        .set(dsl::consumed_at.eq(Utc::now().naive_utc()))
+        .execute(&mut conn)
+        .await
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use actix_web::{web, Error};
+use diesel::result::Error as DieselError;
+use diesel::{ExpressionMethods, JoinOnDsl, QueryDsl, SelectableHelper};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::domain::models::rbac::{NewPermission, Permission, RolePermission};
+use crate::infrastructure::database::schemas::schemas::{permissions, role_permissions};
+use crate::utils::errors::AppError;
+
+pub struct PermissionRepository {
+    pool: web::Data<DbPool>,
+}
+
+impl PermissionRepository {
+    pub fn new(pool: web::Data<DbPool>) -> Self {
+        PermissionRepository { pool }
+    }
+
+    pub async fn create(&self, data: NewPermission) -> Result<Permission, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let permission = diesel::insert_into(permissions::table)
+            .values(&data)
+            .get_result::<Permission>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(permission)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<Permission>, Error> {
+        let query = permissions::table.order(permissions::name.asc());
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let permissions = query
+            .load::<Permission>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(permissions)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Permission, Error> {
+        let query = permissions::table.filter(permissions::id.eq(id));
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let permission = query
+            .first::<Permission>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(permission)
+    }
+
+    pub async fn update(&self, id: Uuid, data: NewPermission) -> Result<Permission, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let permission = diesel::update(permissions::table.filter(permissions::id.eq(id)))
+            .set((&data, permissions::updated_at.eq(chrono::Utc::now().naive_utc())))
+            .get_result::<Permission>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(permission)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let deleted = conn
+            .transaction::<_, DieselError, _>(|conn| {
+                async move {
+                    diesel::delete(role_permissions::table.filter(role_permissions::permission_id.eq(id)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(permissions::table.filter(permissions::id.eq(id))).execute(conn).await
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(AppError::from)?;
+        if deleted == 0 {
+            return Err(AppError::NotFound("Permission not found".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Grants held directly by any of `role_ids`, paired with the permission name.
+    pub async fn grants_for_roles(&self, role_ids: Vec<Uuid>) -> Result<Vec<(String, RolePermission)>, Error> {
+        let query = role_permissions::table
+            .inner_join(permissions::table.on(permissions::id.eq(role_permissions::permission_id)))
+            .filter(role_permissions::role_id.eq_any(role_ids))
+            .select((permissions::name, RolePermission::as_select()));
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let grants = query
+            .load::<(String, RolePermission)>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(grants)
+    }
+}
@@ -0,0 +1,253 @@
+use actix_web::{web, Error};
+use diesel::result::Error as DieselError;
+use diesel::upsert::excluded;
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::domain::models::rbac::{
+    NewAccountRole, NewRole, NewRoleParent, NewRolePermission, Role, RoleParent, RolePermission,
+};
+use crate::infrastructure::database::schemas::schemas::{
+    account_roles, permissions, role_parents, role_permissions, roles,
+};
+use crate::utils::errors::AppError;
+
+pub struct RoleRepository {
+    pool: web::Data<DbPool>,
+}
+
+impl RoleRepository {
+    pub fn new(pool: web::Data<DbPool>) -> Self {
+        RoleRepository { pool }
+    }
+
+    pub async fn create(&self, data: NewRole) -> Result<Role, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let role = diesel::insert_into(roles::table)
+            .values(&data)
+            .get_result::<Role>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(role)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<Role>, Error> {
+        let query = roles::table.order(roles::name.asc());
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let roles = query
+            .load::<Role>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(roles)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Role, Error> {
+        let query = roles::table.filter(roles::id.eq(id));
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let role = query
+            .first::<Role>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(role)
+    }
+
+    pub async fn update(&self, id: Uuid, data: NewRole) -> Result<Role, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let role = diesel::update(roles::table.filter(roles::id.eq(id)))
+            .set((&data, roles::updated_at.eq(chrono::Utc::now().naive_utc())))
+            .get_result::<Role>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(role)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let deleted = conn
+            .transaction::<_, DieselError, _>(|conn| {
+                async move {
+                    diesel::delete(account_roles::table.filter(account_roles::role_id.eq(id))).execute(conn).await?;
+                    diesel::delete(role_permissions::table.filter(role_permissions::role_id.eq(id)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(
+                        role_parents::table.filter(role_parents::role_id.eq(id).or(role_parents::parent_id.eq(id))),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(roles::table.filter(roles::id.eq(id))).execute(conn).await
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(AppError::from)?;
+        if deleted == 0 {
+            return Err(AppError::NotFound("Role not found".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Permission grants of a role, paired with the permission name.
+    pub async fn find_grants(&self, role_id: Uuid) -> Result<Vec<(String, RolePermission)>, Error> {
+        let query = role_permissions::table
+            .inner_join(permissions::table)
+            .filter(role_permissions::role_id.eq(role_id))
+            .order(permissions::name.asc())
+            .select((permissions::name, RolePermission::as_select()));
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let grants = query
+            .load::<(String, RolePermission)>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(grants)
+    }
+
+    /// The whole inheritance graph; role tables stay small enough to walk in memory.
+    pub async fn find_parent_links(&self) -> Result<Vec<RoleParent>, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let links = role_parents::table
+            .load::<RoleParent>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(links)
+    }
+
+    /// Holds off other changes to the role hierarchy until the transaction on
+    /// `conn` ends, so a cycle check still holds when its link is inserted.
+    /// Reads go on as usual.
+    pub(crate) async fn lock_hierarchy(conn: &mut AsyncPgConnection) -> Result<Vec<RoleParent>, AppError> {
+        diesel::sql_query("LOCK TABLE role_parents IN SHARE ROW EXCLUSIVE MODE")
+            .execute(conn)
+            .await
+            .map_err(AppError::from)?;
+        role_parents::table.load::<RoleParent>(conn).await.map_err(AppError::from)
+    }
+
+    /// Makes `role_id` inherit from `parent_id` on `conn`; linking twice is a no-op.
+    pub(crate) async fn insert_parent(
+        conn: &mut AsyncPgConnection,
+        role_id: Uuid,
+        parent_id: Uuid,
+    ) -> Result<(), AppError> {
+        diesel::insert_into(role_parents::table)
+            .values(&NewRoleParent { role_id, parent_id })
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    pub async fn remove_parent(&self, role_id: Uuid, parent_id: Uuid) -> Result<(), Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let deleted = diesel::delete(
+            role_parents::table
+                .filter(role_parents::role_id.eq(role_id))
+                .filter(role_parents::parent_id.eq(parent_id)),
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(AppError::from)?;
+        if deleted == 0 {
+            return Err(AppError::NotFound("The role does not inherit from this role".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Roles currently assigned to an account.
+    pub async fn find_by_account(&self, account_id: Uuid) -> Result<Vec<Role>, Error> {
+        let query = account_roles::table
+            .inner_join(roles::table)
+            .filter(account_roles::account_id.eq(account_id))
+            .select(Role::as_select());
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let roles = query
+            .load::<Role>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(roles)
+    }
+
+    /// Loads a role on `conn`, keeping it from being deleted until the
+    /// surrounding [`UnitOfWork`](crate::infrastructure::database::unit_of_work::UnitOfWork) ends.
+    pub(crate) async fn lock_role(conn: &mut AsyncPgConnection, id: Uuid) -> Result<Role, AppError> {
+        roles::table
+            .filter(roles::id.eq(id))
+            .for_share()
+            .first::<Role>(conn)
+            .await
+            .map_err(|e| match e {
+                DieselError::NotFound => AppError::NotFound("Role not found".to_string()),
+                e => AppError::from(e),
+            })
+    }
+
+    /// Gives the account the role on `conn`; assigning it twice is a no-op.
+    pub(crate) async fn insert_assignment(
+        conn: &mut AsyncPgConnection,
+        account_id: Uuid,
+        role_id: Uuid,
+    ) -> Result<(), AppError> {
+        diesel::insert_into(account_roles::table)
+            .values(&NewAccountRole { account_id, role_id })
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    pub async fn revoke(&self, account_id: Uuid, role_id: Uuid) -> Result<(), Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let deleted = diesel::delete(
+            account_roles::table
+                .filter(account_roles::account_id.eq(account_id))
+                .filter(account_roles::role_id.eq(role_id)),
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(AppError::from)?;
+        if deleted == 0 {
+            return Err(AppError::NotFound("The account does not have this role".to_string()).into());
+        }
+        Ok(())
+    }
+
+    pub async fn remove_grant(&self, role_id: Uuid, permission_id: Uuid) -> Result<(), Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let deleted = diesel::delete(
+            role_permissions::table
+                .filter(role_permissions::role_id.eq(role_id))
+                .filter(role_permissions::permission_id.eq(permission_id)),
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(AppError::from)?;
+        if deleted == 0 {
+            return Err(AppError::NotFound("The role has no grant on this permission".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Sets the action flags a role has on a permission, replacing earlier ones.
+    pub async fn grant(&self, data: NewRolePermission) -> Result<RolePermission, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let grant = diesel::insert_into(role_permissions::table)
+            .values(&data)
+            .on_conflict((role_permissions::role_id, role_permissions::permission_id))
+            .do_update()
+            .set((
+                role_permissions::read.eq(excluded(role_permissions::read)),
+                role_permissions::write.eq(excluded(role_permissions::write)),
+                role_permissions::update.eq(excluded(role_permissions::update)),
+                role_permissions::delete.eq(excluded(role_permissions::delete)),
+            ))
+            .get_result::<RolePermission>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(grant)
+    }
+}
@@ -1,28 +1,719 @@
 use actix_web::{web, Error};
 use diesel::associations::HasTable;
+use diesel::result::Error as DieselError;
+use diesel::sql_types::{Bool, Float, Nullable, Text, Varchar};
+use diesel::pg::Pg;
 use diesel::{
-    debug_query, ExpressionMethods, JoinOnDsl, NullableExpressionMethods, QueryDsl,
-    RunQueryDsl, SelectableHelper, TextExpressionMethods,
+    define_sql_function, BoolExpressionMethods, ExpressionMethods, IntoSql, JoinOnDsl, NullableExpressionMethods,
+    OptionalExtension, PgSortExpressionMethods, QueryDsl, SelectableHelper, TextExpressionMethods,
 };
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 
 use crate::config::database::DbPool;
-use crate::domain::models::user::{NewPasswordHash, NewUser, PasswordHash, User};
+use crate::domain::models::user::{
+    AccountCursor, AccountStatusEnum, KeysetDirection, NewPasswordHash, NewStatusChange, NewUser, PasswordHash,
+    SortOrder, StatusChange, User, UserChangeset, UserFilter, UserSortField,
+};
 use crate::domain::repository::Repository;
+use crate::infrastructure::database::unit_of_work::UnitOfWork;
 use crate::infrastructure::database::schemas::schemas::accounts::dsl;
 use crate::infrastructure::database::schemas::schemas::accounts::dsl::accounts;
-use crate::infrastructure::database::schemas::schemas::password_hashes;
+use crate::infrastructure::database::schemas::schemas::{
+    self, account_roles, account_status_history, auth_tokens, magic_links, otp_codes, password_hashes, password_reset_tokens,
+    two_factor_methods, webauthn_challenges, webauthn_credentials,
+};
 use crate::utils::crypto::{ArgonHash, Password};
 use crate::utils::errors::AppError;
 use uuid::Uuid;
 
+define_sql_function! {
+    fn lower(x: Varchar) -> Varchar;
+}
+
+define_sql_function! {
+    /// `pg_trgm` similarity between 0 and 1.
+    fn similarity(x: Varchar, y: Varchar) -> Float;
+}
+
+define_sql_function! {
+    fn greatest(a: Float, b: Float, c: Float) -> Float;
+}
+
+define_sql_function! {
+    fn coalesce(x: Nullable<Varchar>, y: Varchar) -> Varchar;
+}
+
+// `pg_trgm`'s similarity operator; unlike a `similarity(..) >= x` comparison
+// it can be answered from the trigram indexes on the searched columns.
+diesel::infix_operator!(TrigramMatch, " % ", Bool, backend: Pg);
+
+/// Matches scoring at least this are returned by the fuzzy search even when
+/// they contain no exact substring; it is `pg_trgm`'s own default. Applied
+/// to the `%` operator through `pg_trgm.similarity_threshold`.
+const SIMILARITY_THRESHOLD: f32 = 0.3;
+
+/// Escapes `%`, `_` and the escape character itself so user input only ever
+/// matches literally inside a `LIKE` pattern.
+pub(crate) fn escape_like(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn contains_pattern(term: &str) -> String {
+    format!("%{}%", escape_like(&term.to_lowercase()))
+}
+
 pub struct UserRepository {
     pool: web::Data<DbPool>,
 }
 
+/// Canonical form stored for new and updated accounts. Emails are compared
+/// lower-cased; usernames keep their casing but are unique regardless of it.
+fn normalize(entry: &mut NewUser) -> Result<(), AppError> {
+    entry.username = entry.username.trim().to_string();
+    entry.email = entry.email.trim().to_lowercase();
+    if entry.username.is_empty() || entry.username.contains('@') {
+        return Err(AppError::ValidationError(
+            "Username must not be empty or contain '@'".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn normalize_changes(changes: &mut UserChangeset) -> Result<(), AppError> {
+    if let Some(username) = changes.username.as_mut() {
+        *username = username.trim().to_string();
+        if username.is_empty() || username.contains('@') {
+            return Err(AppError::ValidationError(
+                "Username must not be empty or contain '@'".to_string(),
+            ));
+        }
+    }
+    if let Some(email) = changes.email.as_mut() {
+        *email = email.trim().to_lowercase();
+    }
+    Ok(())
+}
+
+/// Like the generic conversion, but names the missing record.
+fn map_account_error(e: DieselError) -> AppError {
+    match e {
+        DieselError::NotFound => AppError::NotFound("User not found".to_string()),
+        e => AppError::from(e),
+    }
+}
+
+/// Locks a live account for the rest of the transaction and returns its
+/// status, failing with `412` unless its version is one of `expected` (any
+/// version when `None`).
+async fn lock_version(
+    conn: &mut AsyncPgConnection,
+    id: Uuid,
+    expected: Option<&[i32]>,
+) -> Result<AccountStatusEnum, AppError> {
+    let (status, version) = accounts
+        .filter(dsl::id.eq(id))
+        .filter(dsl::deleted_at.is_null())
+        .select((dsl::status, dsl::version))
+        .for_update()
+        .first::<(AccountStatusEnum, i32)>(conn)
+        .await
+        .map_err(map_account_error)?;
+    match expected {
+        Some(expected) if !expected.contains(&version) => Err(AppError::PreconditionFailed(
+            "The account changed since it was read; fetch it again and retry".to_string(),
+        )),
+        _ => Ok(status),
+    }
+}
+
+type AccountQuery = schemas::accounts::BoxedQuery<'static, Pg>;
+
+/// Soft-deleted accounts only show up when they are asked for by status.
+fn filtered(filter: UserFilter) -> AccountQuery {
+    let mut query = match filter.status {
+        Some(AccountStatusEnum::Deleted) => accounts.filter(dsl::deleted_at.is_not_null()).into_boxed(),
+        _ => accounts.filter(dsl::deleted_at.is_null()).into_boxed(),
+    };
+    if let Some(search) = filter.search {
+        let pattern = contains_pattern(&search);
+        query = query.filter(
+            lower(dsl::username)
+                .like(pattern.clone())
+                .or(lower(dsl::email).like(pattern.clone()))
+                .or(dsl::phone_number.like(pattern)),
+        );
+    }
+    if let Some(status) = filter.status {
+        query = query.filter(dsl::status.eq(status));
+    }
+    if let Some(is_active) = filter.is_active {
+        query = query.filter(dsl::is_active.eq(is_active));
+    }
+    if let Some(is_verified) = filter.is_verified {
+        query = query.filter(dsl::is_verified.eq(is_verified));
+    }
+    if let Some(method) = filter.two_factor_method {
+        query = query.filter(dsl::two_factor_method.eq(method));
+    }
+    if let Some(from) = filter.registered_from {
+        query = query.filter(dsl::registration_date.ge(from));
+    }
+    if let Some(before) = filter.registered_before {
+        query = query.filter(dsl::registration_date.lt(before));
+    }
+    query
+}
+
+fn sorted(
+    query: AccountQuery,
+    field: UserSortField,
+    order: SortOrder,
+) -> AccountQuery {
+    let query = match (field, order) {
+        (UserSortField::Username, SortOrder::Asc) => query.order(dsl::username.asc()),
+        (UserSortField::Username, SortOrder::Desc) => query.order(dsl::username.desc()),
+        (UserSortField::Email, SortOrder::Asc) => query.order(dsl::email.asc()),
+        (UserSortField::Email, SortOrder::Desc) => query.order(dsl::email.desc()),
+        (UserSortField::RegistrationDate, SortOrder::Asc) => query.order(dsl::registration_date.asc()),
+        (UserSortField::RegistrationDate, SortOrder::Desc) => query.order(dsl::registration_date.desc()),
+        (UserSortField::LastLogin, SortOrder::Asc) => query.order(dsl::last_login.asc().nulls_last()),
+        (UserSortField::LastLogin, SortOrder::Desc) => query.order(dsl::last_login.desc().nulls_last()),
+        (UserSortField::CreatedAt, SortOrder::Asc) => query.order(dsl::created_at.asc()),
+        (UserSortField::CreatedAt, SortOrder::Desc) => query.order(dsl::created_at.desc()),
+    };
+    // Ties are broken by id so pages neither repeat nor skip rows.
+    query.then_order_by(dsl::id.asc())
+}
+
+/// Rows strictly past `cursor` when walking `(created_at, id)` ascending or
+/// descending, in walking order.
+fn keyset(query: AccountQuery, cursor: Option<AccountCursor>, ascending: bool) -> AccountQuery {
+    let query = match cursor {
+        Some(cursor) if ascending => query.filter(
+            dsl::created_at
+                .gt(cursor.created_at)
+                .or(dsl::created_at.eq(cursor.created_at).and(dsl::id.gt(cursor.id))),
+        ),
+        Some(cursor) => query.filter(
+            dsl::created_at
+                .lt(cursor.created_at)
+                .or(dsl::created_at.eq(cursor.created_at).and(dsl::id.lt(cursor.id))),
+        ),
+        None => query,
+    };
+    if ascending {
+        query.order((dsl::created_at.asc(), dsl::id.asc()))
+    } else {
+        query.order((dsl::created_at.desc(), dsl::id.desc()))
+    }
+}
+
 impl UserRepository {
     pub fn new(pool: web::Data<DbPool>) -> Self {
         UserRepository { pool }
     }
+
+    /// Accounts whose username, email or phone number contain `term` or are
+    /// similar to it, best matches first, each with its similarity score.
+    pub async fn search(&self, term: &str, limit: i64) -> Result<Vec<(User, f32)>, Error> {
+        let pattern = contains_pattern(term);
+        let term = term.to_lowercase();
+        let score = || {
+            greatest(
+                similarity(lower(dsl::username), term.clone()),
+                similarity(lower(dsl::email), term.clone()),
+                similarity(coalesce(dsl::phone_number, ""), term.clone()),
+            )
+        };
+        // Each branch uses exactly the expression one of the trigram indexes
+        // is built on, so the planner can OR their bitmap scans together.
+        let query = accounts
+            .filter(dsl::deleted_at.is_null())
+            .filter(
+                lower(dsl::username)
+                    .like(pattern.clone())
+                    .or(lower(dsl::email).like(pattern.clone()))
+                    .or(dsl::phone_number.like(pattern))
+                    .or(TrigramMatch::new(lower(dsl::username), term.clone().into_sql::<Varchar>()))
+                    .or(TrigramMatch::new(lower(dsl::email), term.clone().into_sql::<Varchar>()))
+                    .or(TrigramMatch::new(dsl::phone_number, term.clone().into_sql::<Varchar>())),
+            )
+            .select((User::as_select(), score()))
+            .order((score().desc(), dsl::username.asc()))
+            .limit(limit);
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let users = conn
+            .transaction::<_, DieselError, _>(|conn| {
+                async move {
+                    // Local to this transaction, so pooled connections keep
+                    // the server default.
+                    diesel::sql_query("SELECT set_config('pg_trgm.similarity_threshold', $1, true)")
+                        .bind::<Text, _>(SIMILARITY_THRESHOLD.to_string())
+                        .execute(conn)
+                        .await?;
+                    query.load::<(User, f32)>(conn).await
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(AppError::from)?;
+        Ok(users)
+    }
+
+    /// Up to `limit` accounts matching `filter` next to `cursor` (from the
+    /// start of the listing without one). Rows before the cursor come back
+    /// nearest first.
+    pub async fn find_by_keyset(
+        &self,
+        filter: UserFilter,
+        order: SortOrder,
+        cursor: Option<AccountCursor>,
+        limit: i64,
+    ) -> Result<Vec<User>, Error> {
+        let backwards = cursor.is_some_and(|c| c.direction == KeysetDirection::Before);
+        let ascending = (order == SortOrder::Asc) != backwards;
+        let query = keyset(filtered(filter), cursor, ascending)
+            .select(User::as_select())
+            .limit(limit);
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let users = query
+            .load::<User>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(users)
+    }
+
+    /// One page of the accounts matching `filter`, plus how many match in total.
+    pub async fn find_page(
+        &self,
+        filter: UserFilter,
+        sort_by: UserSortField,
+        order: SortOrder,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<User>, i64), Error> {
+        let count = filtered(filter.clone()).count();
+        let page = sorted(filtered(filter), sort_by, order)
+            .select(User::as_select())
+            .limit(limit)
+            .offset(offset);
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let total = count
+            .get_result::<i64>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        let users = page
+            .load::<User>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok((users, total))
+    }
+
+    /// Resolves a login identifier: anything containing `@` is treated as an
+    /// email address, everything else as a username. Both match case-insensitively.
+    pub async fn find_by_login(&self, identifier: &str) -> Result<(User, Option<PasswordHash>), Error> {
+        if identifier.contains('@') {
+            let user = self.find_by_email(identifier).await?;
+            return self.find_by_username(&user.username).await;
+        }
+        self.find_by_username(identifier).await
+    }
+
+    /// Changes only the columns set in `changes`. Fails
+    /// with `412` unless the account is at one of `expected_versions` (any
+    /// version when `None`).
+    pub async fn update_fields(
+        &self,
+        id: Uuid,
+        expected_versions: Option<Vec<i32>>,
+        mut changes: UserChangeset,
+    ) -> Result<User, Error> {
+        normalize_changes(&mut changes)?;
+        changes.updated_at = Some(chrono::Utc::now().naive_utc());
+        UnitOfWork::new(self.pool.clone())
+            .run(move |conn| {
+                async move {
+                    lock_version(conn, id, expected_versions.as_deref()).await?;
+                    diesel::update(accounts.filter(dsl::id.eq(id)))
+                        .set((&changes, dsl::version.eq(dsl::version + 1)))
+                        .get_result::<User>(conn)
+                        .await
+                        .map_err(map_account_error)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    pub async fn set_phone_verified(&self, id: Uuid, verified: bool) -> Result<User, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let user = diesel::update(accounts.filter(dsl::id.eq(id)).filter(dsl::deleted_at.is_null()))
+            .set((dsl::phone_verified.eq(verified), dsl::version.eq(dsl::version + 1)))
+            .get_result::<User>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
+    }
+
+    /// Counts a failed sign in. Once `max_attempts` consecutive failures are
+    /// reached the account is locked until `lock_until` and the counter restarts.
+    pub async fn record_failed_login(
+        &self,
+        id: Uuid,
+        max_attempts: i32,
+        lock_until: chrono::NaiveDateTime,
+    ) -> Result<User, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let user = diesel::update(accounts.filter(dsl::id.eq(id)))
+            .set(dsl::login_attempts.eq(dsl::login_attempts + 1))
+            .get_result::<User>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        if user.login_attempts < max_attempts {
+            return Ok(user);
+        }
+        let user = diesel::update(accounts.filter(dsl::id.eq(id)))
+            .set((dsl::login_attempts.eq(0), dsl::locked_until.eq(Some(lock_until))))
+            .get_result::<User>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
+    }
+
+    pub async fn record_successful_login(&self, id: Uuid) -> Result<User, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let user = diesel::update(accounts.filter(dsl::id.eq(id)))
+            .set((
+                dsl::login_attempts.eq(0),
+                dsl::locked_until.eq(None::<chrono::NaiveDateTime>),
+                dsl::last_login.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .get_result::<User>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
+    }
+
+    /// Brings back an account soft deleted at or after `deleted_since`, in
+    /// the status it had before the deletion.
+    pub async fn restore(
+        &self,
+        id: Uuid,
+        deleted_since: chrono::NaiveDateTime,
+        restored_by: Uuid,
+    ) -> Result<User, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let deleted_at = accounts
+            .filter(dsl::id.eq(id))
+            .filter(dsl::deleted_at.is_not_null())
+            .select(dsl::deleted_at)
+            .first::<Option<chrono::NaiveDateTime>>(&mut conn)
+            .await
+            .map_err(|e| match e {
+                DieselError::NotFound => AppError::NotFound("No deleted user with this id".to_string()),
+                e => map_account_error(e),
+            })?;
+        if deleted_at.is_some_and(|at| at < deleted_since) {
+            return Err(AppError::BadRequest(
+                "The restore period for this account has expired".to_string(),
+            )
+            .into());
+        }
+        let user = conn
+            .transaction(|conn| {
+                async move {
+                    // The deletion recorded where the account came from;
+                    // accounts deleted without a history entry come back active.
+                    let status = account_status_history::table
+                        .filter(account_status_history::account_id.eq(id))
+                        .filter(account_status_history::to_status.eq(AccountStatusEnum::Deleted))
+                        .order((account_status_history::changed_at.desc(), account_status_history::id.desc()))
+                        .select(account_status_history::from_status)
+                        .first::<AccountStatusEnum>(conn)
+                        .await
+                        .optional()?
+                        .unwrap_or(AccountStatusEnum::Active);
+                    let user = diesel::update(accounts.filter(dsl::id.eq(id)).filter(dsl::deleted_at.is_not_null()))
+                        .set((
+                            dsl::status.eq(status),
+                            dsl::is_active.eq(status == AccountStatusEnum::Active),
+                            dsl::deleted_at.eq(None::<chrono::NaiveDateTime>),
+                            dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+                            dsl::version.eq(dsl::version + 1),
+                        ))
+                        .get_result::<User>(conn)
+                        .await?;
+                    diesel::insert_into(account_status_history::table)
+                        .values(&NewStatusChange {
+                            account_id: id,
+                            from_status: AccountStatusEnum::Deleted,
+                            to_status: status,
+                            reason: "Account restored".to_string(),
+                            changed_by: Some(restored_by),
+                        })
+                        .execute(conn)
+                        .await?;
+                    Ok(user)
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
+    }
+
+    /// Soft deletes the account, recording who did it in its status history.
+    /// Like [`UserRepository::update_fields`], it only goes ahead while the
+    /// account is at one of `expected_versions`.
+    pub async fn soft_delete(
+        &self,
+        id: Uuid,
+        deleted_by: Option<Uuid>,
+        expected_versions: Option<Vec<i32>>,
+    ) -> Result<(), Error> {
+        UnitOfWork::new(self.pool.clone())
+            .run(move |conn| {
+                async move {
+                    let status = lock_version(conn, id, expected_versions.as_deref()).await?;
+                    let now = chrono::Utc::now().naive_utc();
+                    diesel::update(accounts.filter(dsl::id.eq(id)))
+                        .set((
+                            dsl::status.eq(AccountStatusEnum::Deleted),
+                            dsl::is_active.eq(false),
+                            dsl::deleted_at.eq(Some(now)),
+                            dsl::updated_at.eq(now),
+                            dsl::version.eq(dsl::version + 1),
+                        ))
+                        .execute(conn)
+                        .await?;
+                    diesel::insert_into(account_status_history::table)
+                        .values(&NewStatusChange {
+                            account_id: id,
+                            from_status: status,
+                            to_status: AccountStatusEnum::Deleted,
+                            reason: "Account deleted".to_string(),
+                            changed_by: deleted_by,
+                        })
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Moves the account from `change.from_status` to `change.to_status` and
+    /// records the change. Fails if the status moved on in the meantime.
+    pub async fn change_status(&self, change: NewStatusChange) -> Result<User, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let id = change.account_id;
+        let active = change.to_status == AccountStatusEnum::Active;
+        let user = conn
+            .transaction(|conn| {
+                async move {
+                    let target = accounts
+                        .filter(dsl::id.eq(id))
+                        .filter(dsl::status.eq(change.from_status))
+                        .filter(dsl::deleted_at.is_null());
+                    let mut user = diesel::update(target)
+                        .set((
+                            dsl::status.eq(change.to_status),
+                            dsl::is_active.eq(active),
+                            dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+                            dsl::version.eq(dsl::version + 1),
+                        ))
+                        .get_result::<User>(conn)
+                        .await?;
+                    if active {
+                        // Coming back also lifts any temporary sign in lockout.
+                        user = diesel::update(accounts.filter(dsl::id.eq(id)))
+                            .set((dsl::login_attempts.eq(0), dsl::locked_until.eq(None::<chrono::NaiveDateTime>)))
+                            .get_result::<User>(conn)
+                            .await?;
+                    }
+                    diesel::insert_into(account_status_history::table)
+                        .values(&change)
+                        .execute(conn)
+                        .await?;
+                    Ok(user)
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(|e| match e {
+                DieselError::NotFound => AppError::BadRequest(
+                    "The account status changed meanwhile, try again".to_string(),
+                ),
+                e => map_account_error(e),
+            })?;
+        Ok(user)
+    }
+
+    /// Status changes of an account, oldest first.
+    pub async fn status_history(&self, id: Uuid) -> Result<Vec<StatusChange>, Error> {
+        let query = account_status_history::table
+            .filter(account_status_history::account_id.eq(id))
+            .order((account_status_history::changed_at.asc(), account_status_history::id.asc()))
+            .select(StatusChange::as_select());
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let history = query
+            .load::<StatusChange>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(history)
+    }
+
+    /// The account's current status and when it last left `Active`, or `None`
+    /// once it no longer exists.
+    pub async fn session_state(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<(AccountStatusEnum, Option<chrono::NaiveDateTime>)>, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let status = accounts
+            .filter(dsl::id.eq(id))
+            .select(dsl::status)
+            .first::<AccountStatusEnum>(&mut conn)
+            .await
+            .optional()
+            .map_err(AppError::from)?;
+        let Some(status) = status else {
+            return Ok(None);
+        };
+        let left_active = account_status_history::table
+            .filter(account_status_history::account_id.eq(id))
+            .filter(account_status_history::from_status.eq(AccountStatusEnum::Active))
+            .select(diesel::dsl::max(account_status_history::changed_at))
+            .first::<Option<chrono::NaiveDateTime>>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(Some((status, left_active)))
+    }
+
+    /// Creates the account together with the hash of its initial password;
+    /// if either insert fails neither row is kept.
+    pub async fn create_with_password(&self, mut data: NewUser, password: Password) -> Result<User, Error> {
+        normalize(&mut data)?;
+        let hash = web::block(move || password.hash_password()).await?;
+        let parsed = argon2::PasswordHash::new(&hash).map_err(|e| AppError::InternalError(e.to_string()))?;
+        let salt = parsed.salt.map(|salt| salt.as_str().as_bytes().to_vec()).unwrap_or_default();
+        let algorithm = parsed.algorithm.to_string();
+        UnitOfWork::new(self.pool.clone())
+            .run(move |conn| {
+                async move {
+                    let user = UserRepository::insert_account(conn, &data).await?;
+                    UserRepository::insert_password_hash(
+                        conn,
+                        &NewPasswordHash {
+                            user_id: user.id,
+                            password_hash: hash.into_bytes(),
+                            salt,
+                            algorithm,
+                            is_temporary: false,
+                            expiry: chrono::Utc::now().naive_utc() + chrono::Duration::days(265),
+                        },
+                    )
+                    .await?;
+                    Ok(user)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Inserts an account on `conn`, for use inside a [`UnitOfWork`].
+    pub(crate) async fn insert_account(conn: &mut AsyncPgConnection, data: &NewUser) -> Result<User, AppError> {
+        diesel::insert_into(accounts)
+            .values(data)
+            .get_result::<User>(conn)
+            .await
+            .map_err(map_account_error)
+    }
+
+    /// Inserts a password hash on `conn`, for use inside a [`UnitOfWork`].
+    pub(crate) async fn insert_password_hash(
+        conn: &mut AsyncPgConnection,
+        hash: &NewPasswordHash,
+    ) -> Result<(), AppError> {
+        diesel::insert_into(password_hashes::table)
+            .values(hash)
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("password hash insert failed: {}", e);
+                AppError::InternalError("Failed to store the password".to_string())
+            })?;
+        Ok(())
+    }
+
+    /// Loads a live account on `conn`, keeping it from being changed or
+    /// deleted until the surrounding [`UnitOfWork`] ends.
+    pub(crate) async fn lock_account(conn: &mut AsyncPgConnection, id: Uuid) -> Result<User, AppError> {
+        accounts
+            .filter(dsl::id.eq(id))
+            .filter(dsl::deleted_at.is_null())
+            .for_share()
+            .first::<User>(conn)
+            .await
+            .map_err(map_account_error)
+    }
+
+    /// Hard deletes accounts soft deleted before `before`, together with
+    /// everything they own, and returns how many accounts went.
+    pub async fn purge_deleted(&self, before: chrono::NaiveDateTime) -> Result<usize, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let purged = conn
+            .transaction::<_, DieselError, _>(|conn| {
+                async move {
+                    let ids = accounts
+                        .filter(dsl::deleted_at.lt(before))
+                        .select(dsl::id)
+                        .for_update()
+                        .load::<Uuid>(conn)
+                        .await?;
+                    if ids.is_empty() {
+                        return Ok(0);
+                    }
+                    diesel::delete(account_roles::table.filter(account_roles::account_id.eq_any(&ids)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(password_hashes::table.filter(password_hashes::user_id.eq_any(&ids)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(auth_tokens::table.filter(auth_tokens::sub.eq_any(&ids))).execute(conn).await?;
+                    diesel::delete(password_reset_tokens::table.filter(password_reset_tokens::user_id.eq_any(&ids)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(two_factor_methods::table.filter(two_factor_methods::user_id.eq_any(&ids)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(otp_codes::table.filter(otp_codes::user_id.eq_any(&ids))).execute(conn).await?;
+                    diesel::delete(magic_links::table.filter(magic_links::user_id.eq_any(&ids)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(webauthn_credentials::table.filter(webauthn_credentials::user_id.eq_any(&ids)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(webauthn_challenges::table.filter(webauthn_challenges::user_id.eq_any(&ids)))
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(accounts.filter(dsl::id.eq_any(&ids))).execute(conn).await
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(AppError::from)?;
+        Ok(purged)
+    }
 }
 #[async_trait::async_trait]
 impl Repository<User, Uuid, NewUser, NewUser, (User, Option<PasswordHash>)> for UserRepository {
@@ -32,88 +723,63 @@ impl Repository<User, Uuid, NewUser, NewUser, (User, Option<PasswordHash>)> for
         offsets: Option<i16>,
         search: Option<String>,
     ) -> Result<Vec<User>, Error> {
-        let pool = self.pool.clone();
-        let mut query = accounts
-            .select(User::as_select())
-            .into_boxed() // This boxes the query
+        let query = filtered(UserFilter { search, ..UserFilter::default() })
+            .order(dsl::registration_date.asc())
+            .then_order_by(dsl::id.asc())
             .limit(limit.unwrap_or(10) as i64)
-            .offset(offsets.unwrap_or(0) as i64);
-
-        let filtered = match search {
-            Some(search) => query.filter(dsl::username.like(format!("%{}%", search.to_string()))),
-            None => query,
-        };
-
-        println!("{:?}", debug_query(&filtered));
-        let mut conn = pool
-            .get()
-            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
-
-        let users = web::block(move || {
-            let data = filtered
-                .load::<User>(&mut conn)
-                .expect("Error loading users");
-            data
-        })
-        .await?;
+            .offset(offsets.unwrap_or(0) as i64)
+            .select(User::as_select());
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let users = query
+            .load::<User>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
         Ok(users)
     }
 
     async fn find_by_id(&self, id: Uuid) -> Result<User, Error> {
-        let query = accounts.filter(dsl::id.eq(id));
-        let mut conn = self
-            .pool
-            .get()
-            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
-        let user = web::block(move || {
-            query
-                .first::<User>(&mut conn)
-                .map_err(|e| AppError::NotFound(e.to_string()))
-        })
-        .await?;
-        Ok(user?)
+        let query = accounts.filter(dsl::id.eq(id)).filter(dsl::deleted_at.is_null());
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let user = query
+            .first::<User>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
     }
 
     async fn find_by(&self, id: Uuid) -> Result<User, Error> {
-        let query = accounts.filter(dsl::id.eq(id));
-        let mut conn = self
-            .pool
-            .get()
-            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
-        let user = web::block(move || {
-            query
-                .first::<User>(&mut conn)
-                .map_err(|e| AppError::NotFound(e.to_string()))
-        })
-        .await?;
-        Ok(user?)
+        let query = accounts.filter(dsl::id.eq(id)).filter(dsl::deleted_at.is_null());
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let user = query
+            .first::<User>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
     }
 
     async fn find_by_email(&self, email: &str) -> Result<User, Error> {
-        let query = accounts.filter(dsl::email.eq(email.to_string()));
-        let mut conn = self
-            .pool
-            .get()
-            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
-        let user = web::block(move || {
-            query
-                .first::<User>(&mut conn)
-                .map_err(|e| AppError::NotFound(e.to_string()))
-        })
-        .await?;
-        Ok(user?)
+        let query = accounts
+            .filter(lower(dsl::email).eq(email.trim().to_lowercase()))
+            .filter(dsl::deleted_at.is_null());
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let user = query
+            .first::<User>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
     }
     async fn find_by_username(
         &self,
         username: &str,
     ) -> Result<(User, Option<PasswordHash>), Error> {
-        let username = username.to_string();
+        let username = username.trim().to_lowercase();
         let query = accounts
             .left_outer_join(
                 password_hashes::dsl::password_hashes::table()
                     .on(dsl::id.eq(password_hashes::dsl::user_id)),
             )
-            .filter(dsl::username.eq(username))
+            .filter(lower(dsl::username).eq(username))
+            .filter(dsl::deleted_at.is_null())
             .select((
                 User::as_select(),
                 (
@@ -130,83 +796,104 @@ impl Repository<User, Uuid, NewUser, NewUser, (User, Option<PasswordHash>)> for
                 )
                     .nullable(),
             ));
-        let mut conn = self
-            .pool
-            .get()
-            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
-        let user = web::block(move || {
-            query
-                .first::<(User, Option<PasswordHash>)>(&mut conn)
-                .map_err(|e| AppError::NotFound(e.to_string()))
-        })
-        .await?;
-        Ok(user?)
-    }
-
-    async fn create(&self, data: NewUser) -> Result<User, Error> {
-        let pool = self.pool.clone();
-        let new_user = data.clone();
-        let mut conn = pool
-            .get()
-            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
-        let user = diesel::insert_into(accounts)
-            .values(&new_user)
-            .get_result::<User>(&mut conn);
-
-        match user {
-            Ok(user) => {
-                let password = Password {
-                    plain: data.username,
-                };
-                let hash = password.hash_password();
-                let password_hash = NewPasswordHash {
-                    user_id: user.id,
-                    password_hash: hash.into_bytes(),
-                    salt: "SALT".to_string().into_bytes(),
-                    algorithm: "ALGO".to_string(),
-                    is_temporary: false,
-                    expiry: chrono::Utc::now().naive_utc() + chrono::Duration::days(265),
-                };
-                diesel::insert_into(password_hashes::dsl::password_hashes)
-                    .values(&password_hash)
-                    .execute(&mut conn)
-                    .expect("Error inserting password hash");
-                Ok(user)
-            }
-            Err(e) => {
-                return Err(Error::from(AppError::NotFound(e.to_string())));
-            }
-        }
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let user = query
+            .first::<(User, Option<PasswordHash>)>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
     }
 
-    async fn update(&self, id: Uuid, entry: NewUser) -> Result<User, Error> {
-        let pool = self.pool.clone();
-        let mut conn = pool
-            .get()
-            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
-        let user = diesel::update(accounts.filter(dsl::id.eq(id)));
-        let user = web::block(move || {
-            user
-                .set(&entry)
-                .get_result::<User>(&mut conn)
-                .map_err(|e| AppError::NotFound(e.to_string()))
-        })
-        .await?;
-        Ok(user?)
+    /// Creates the account without a password, so it cannot sign in with
+    /// one; see [`UserRepository::create_with_password`].
+    async fn create(&self, mut data: NewUser) -> Result<User, Error> {
+        normalize(&mut data)?;
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        Ok(UserRepository::insert_account(&mut conn, &data).await?)
+    }
+
+    async fn update(&self, id: Uuid, mut entry: NewUser) -> Result<User, Error> {
+        normalize(&mut entry)?;
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let user = diesel::update(accounts.filter(dsl::id.eq(id)))
+            .set((&entry, dsl::version.eq(dsl::version + 1)))
+            .get_result::<User>(&mut conn)
+            .await
+            .map_err(map_account_error)?;
+        Ok(user)
     }
 
+    /// Soft delete: the account is hidden and deactivated but kept until
+    /// [`UserRepository::purge_deleted`] removes it for good.
     async fn delete(&self, id: Uuid) -> Result<(), Error> {
-        let pool = self.pool.clone();
-        let mut conn = pool
-            .get()
-            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
-        let user = diesel::delete(accounts.filter(dsl::id.eq(id)));
-        web::block(move || {
-            user
-                .execute(&mut conn)
-                .map_err(|e| AppError::NotFound(e.to_string()))
+        self.soft_delete(id, None, None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing;
+
+    #[test]
+    fn like_wildcards_are_escaped() {
+        assert_eq!(escape_like("50%_off"), "50\\%\\_off");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+        assert_eq!(contains_pattern("Ali_"), "%ali\\_%");
+    }
+
+    fn is_not_found(e: &Error) -> bool {
+        matches!(e.as_error::<AppError>(), Some(AppError::NotFound(_)))
+    }
+
+    #[actix_web::test]
+    async fn restore_brings_back_the_status_before_deletion() {
+        let Some(ctx) = testing::context() else { return };
+        let repo = UserRepository::new(ctx.pool.clone());
+        let admin = testing::account(&ctx.pool, "alice").await;
+        let bob = testing::account(&ctx.pool, "bob").await;
+        repo.change_status(NewStatusChange {
+            account_id: bob.id,
+            from_status: AccountStatusEnum::Active,
+            to_status: AccountStatusEnum::Suspended,
+            reason: "Chargeback".to_string(),
+            changed_by: Some(admin.id),
         })
-            .await?.expect("TODO: panic message");
-        Ok(())
+        .await
+        .unwrap();
+
+        assert!(is_not_found(&repo.restore(bob.id, bob.registration_date, admin.id).await.unwrap_err()));
+
+        repo.soft_delete(bob.id, Some(admin.id), None).await.unwrap();
+        assert!(is_not_found(&repo.set_phone_verified(bob.id, true).await.unwrap_err()));
+        let later = chrono::Utc::now().naive_utc() + chrono::Duration::minutes(1);
+        let expired = repo.restore(bob.id, later, admin.id).await.unwrap_err();
+        assert!(matches!(expired.as_error::<AppError>(), Some(AppError::BadRequest(_))));
+
+        let restored = repo.restore(bob.id, bob.registration_date, admin.id).await.unwrap();
+        assert_eq!(restored.status, AccountStatusEnum::Suspended);
+        assert!(!restored.is_active);
+        assert!(restored.deleted_at.is_none());
+        assert_eq!(restored.version, bob.version + 3);
+        assert_eq!(repo.set_phone_verified(bob.id, true).await.unwrap().version, bob.version + 4);
+    }
+
+    #[actix_web::test]
+    async fn purge_deletes_accounts_soft_deleted_before_the_cutoff() {
+        let Some(ctx) = testing::context() else { return };
+        let repo = UserRepository::new(ctx.pool.clone());
+        let bob = testing::account(&ctx.pool, "bob").await;
+        let carol = testing::account(&ctx.pool, "carol").await;
+        repo.soft_delete(bob.id, None, None).await.unwrap();
+
+        let earlier = bob.registration_date - chrono::Duration::minutes(1);
+        assert_eq!(repo.purge_deleted(earlier).await.unwrap(), 0);
+        let later = chrono::Utc::now().naive_utc() + chrono::Duration::minutes(1);
+        assert_eq!(repo.purge_deleted(later).await.unwrap(), 1);
+        assert_eq!(repo.purge_deleted(later).await.unwrap(), 0);
+
+        let gone = repo.restore(bob.id, earlier, carol.id).await.unwrap_err();
+        assert!(is_not_found(&gone));
+        assert_eq!(repo.find_by_id(carol.id).await.unwrap().id, carol.id);
     }
 }
@@ -0,0 +1,91 @@
+use actix_web::{web, Error};
+use chrono::Utc;
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use uuid::Uuid;
+
+use crate::config::database::DbPool;
+use crate::domain::models::webauthn::{
+    NewWebauthnChallenge, NewWebauthnCredential, WebauthnChallenge, WebauthnCredential,
+};
+use crate::infrastructure::database::schemas::schemas::webauthn_challenges;
+use crate::infrastructure::database::schemas::schemas::webauthn_credentials;
+use crate::utils::errors::AppError;
+
+pub struct WebauthnRepository {
+    pool: web::Data<DbPool>,
+}
+
+impl WebauthnRepository {
+    pub fn new(pool: web::Data<DbPool>) -> Self {
+        WebauthnRepository { pool }
+    }
+
+    pub async fn create_challenge(&self, data: NewWebauthnChallenge) -> Result<WebauthnChallenge, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let challenge = diesel::insert_into(webauthn_challenges::table)
+            .values(&data)
+            .get_result::<WebauthnChallenge>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(challenge)
+    }
+
+    /// Removes and returns a pending challenge, so each one can be answered once.
+    pub async fn take_challenge(&self, id: Uuid, ceremony: &str) -> Result<WebauthnChallenge, Error> {
+        let ceremony = ceremony.to_string();
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let challenge = diesel::delete(
+            webauthn_challenges::table
+                .filter(webauthn_challenges::id.eq(id))
+                .filter(webauthn_challenges::ceremony.eq(ceremony)),
+        )
+        .get_result::<WebauthnChallenge>(&mut conn)
+        .await
+        .map_err(|_| AppError::Unauthorized("Unknown or already used ceremony".to_string()))?;
+        Ok(challenge)
+    }
+
+    pub async fn find_credentials_by_user(&self, user_id: Uuid) -> Result<Vec<WebauthnCredential>, Error> {
+        let query = webauthn_credentials::table.filter(webauthn_credentials::user_id.eq(user_id));
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let credentials = query
+            .load::<WebauthnCredential>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(credentials)
+    }
+
+    pub async fn find_credential(&self, credential_id: Vec<u8>) -> Result<WebauthnCredential, Error> {
+        let query = webauthn_credentials::table.filter(webauthn_credentials::credential_id.eq(credential_id));
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let credential = query
+            .first::<WebauthnCredential>(&mut conn)
+            .await
+            .map_err(|_| AppError::Unauthorized("Unknown credential".to_string()))?;
+        Ok(credential)
+    }
+
+    pub async fn create_credential(&self, data: NewWebauthnCredential) -> Result<WebauthnCredential, Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let credential = diesel::insert_into(webauthn_credentials::table)
+            .values(&data)
+            .get_result::<WebauthnCredential>(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(credential)
+    }
+
+    pub async fn record_use(&self, id: Uuid, sign_count: i64) -> Result<(), Error> {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        diesel::update(webauthn_credentials::table.filter(webauthn_credentials::id.eq(id)))
+            .set((
+                webauthn_credentials::sign_count.eq(sign_count),
+                webauthn_credentials::last_used_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(&mut conn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
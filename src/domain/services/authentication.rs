@@ -1,53 +1,315 @@
 use std::collections::HashMap;
-use actix_web::web;
-use chrono::Utc;
+use std::time::Duration;
+use actix_web::{web, Error};
+use chrono::{NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
 use uuid::Uuid;
-use crate::utils::crypto::{Claim, Password, Token, ArgonHash};
+use crate::utils::crypto::{decrypt_token, Claim, Password, Token, ArgonHash};
 use crate::config::database::{DbPool};
-use crate::domain::models::authentication::LoginRequest;
+use crate::config::security::{AUTHORIZATION_SETTINGS, LOGIN_SETTINGS, MAGIC_LINK_SETTINGS};
+use crate::domain::models::authentication::{LoginRequest, MagicLinkIssued, NewMagicLink};
+use crate::domain::models::otp::{OtpChallenge, OtpPurpose};
+use crate::domain::models::user::{AccountStatusEnum, PasswordHash, TwoFactorMethodEnum, User};
+use crate::domain::repositories::magic_link_repository::MagicLinkRepository;
+use crate::domain::repositories::otp_repository::OtpRepository;
 use crate::domain::repositories::user_repository::UserRepository;
 use crate::domain::repository::Repository;
+use crate::domain::models::webauthn::{Ceremony, RequestOptions};
+use crate::domain::services::permission_services::PermissionService;
+use crate::domain::services::{otp_services, webauthn_services};
+use crate::infrastructure::external::otp_sender::{OtpMessage, OtpSenders};
+use crate::utils::cache::TtlCache;
 use crate::utils::errors::AppError;
-use crate::api::dto::requests::auth;
+use crate::utils::otp::{hash_code, verify_code};
+use crate::utils::rate_limit::RateLimiter;
+
+/// Audience of the access and refresh tokens handed out after sign in.
+pub(crate) const ACCESS_TOKEN_AUDIENCE: &str = "audience";
+const MAGIC_LINK_AUDIENCE: &str = "magic-link";
+
+/// Sign in methods recorded in the `amr` claim of access tokens.
+pub(crate) const AMR_PASSWORD: &str = "pwd";
+pub(crate) const AMR_OTP: &str = "otp";
+pub(crate) const AMR_MAGIC_LINK: &str = "magic_link";
+pub(crate) const AMR_WEBAUTHN: &str = "webauthn";
+
+/// An account's status and when it last left `Active`; `None` once it is gone.
+type SessionState = Option<(AccountStatusEnum, Option<NaiveDateTime>)>;
+
+static SESSION_CACHE: Lazy<TtlCache<Uuid, SessionState>> = Lazy::new(|| {
+    TtlCache::new(Duration::from_secs(AUTHORIZATION_SETTINGS.cache_ttl_seconds))
+});
+
+/// Rejects access tokens of accounts that are not `Active`, and tokens issued
+/// before the account last left `Active`, so suspending or locking an account
+/// revokes its tokens for good. Served from the in-process cache when possible.
+pub(crate) async fn ensure_token_usable(
+    pool: web::Data<DbPool>,
+    account_id: Uuid,
+    issued_at: NaiveDateTime,
+) -> Result<(), Error> {
+    let state = match SESSION_CACHE.get(&account_id) {
+        Some(state) => state,
+        None => {
+            let generation = SESSION_CACHE.generation();
+            let state = UserRepository::new(pool).session_state(account_id).await?;
+            SESSION_CACHE.insert(account_id, state, generation);
+            state
+        }
+    };
+    match state {
+        Some((AccountStatusEnum::Active, left_active)) if left_active.is_none_or(|at| issued_at > at) => Ok(()),
+        _ => Err(AppError::Unauthorized("Access token has been revoked".to_string()).into()),
+    }
+}
+
+/// Drops the cached session state of an account after its status changed.
+pub(crate) fn forget_session(account_id: Uuid) {
+    SESSION_CACHE.invalidate(&account_id);
+}
+
+/// Result of a password login: either tokens, or a second factor to complete.
+pub enum LoginOutcome {
+    Tokens(HashMap<String, String>),
+    MfaRequired(OtpChallenge),
+    WebauthnRequired(Ceremony<RequestOptions>),
+}
+
+/// Issues access and refresh tokens, embedding the account's roles and
+/// permissions when `TOKEN_EMBED_PERMISSIONS` is on.
+pub(crate) async fn issue_tokens(
+    pool: web::Data<DbPool>,
+    user: &User,
+    methods: &[&str],
+) -> Result<HashMap<String, String>, Error> {
+    if user.status != AccountStatusEnum::Active {
+        return Err(AppError::Forbidden(format!("Account is {}", user.status.as_str())).into());
+    }
+    let (roles, permissions) = if AUTHORIZATION_SETTINGS.embed_in_tokens {
+        let access = PermissionService::new(pool).access(user.id).await?;
+        (
+            Some(access.roles.clone()),
+            Some(access.permissions.iter().map(|p| p.to_compact()).collect()),
+        )
+    } else {
+        (None, None)
+    };
+    let token = Claim {
+        iss: "localhost".to_string(),
+        jti: Uuid::new_v4().to_string(),
+        aud: ACCESS_TOKEN_AUDIENCE.to_string(),
+        nbf: Utc::now(),
+        exp: Utc::now() + chrono::Duration::days(1),
+        iat: Utc::now(),
+        sub: user.id.to_string(),
+        roles,
+        permissions,
+        amr: Some(methods.iter().map(|method| method.to_string()).collect()),
+    };
+    let access_token = token.generate_token();
+    let refresh_token = token.generate_token();
+    let expires = Utc::now().timestamp() + 60 * 60 * 24 * 7; // 7 days
+    let mut res =  HashMap::new();
+    res.insert("access_token".to_string(), access_token);
+    res.insert("refresh_token".to_string(), refresh_token);
+    res.insert("expires".to_string(), expires.to_string());
+    res.insert("token_type".to_string(), "Bearer".to_string());
+    Ok(res)
+}
+
+/// Applies the per-client and per-identifier request budgets.
+fn throttle(limiter: &RateLimiter, client: &str, identifier: &str) -> Result<(), AppError> {
+    limiter.check(&format!("ip:{}", client))?;
+    limiter.check(&format!("login:{}", identifier.to_lowercase()))
+}
+
+pub(crate) fn ensure_not_locked(user: &User) -> Result<(), AppError> {
+    match user.locked_until {
+        Some(until) if until > Utc::now().naive_utc() => Err(AppError::Forbidden(
+            "Account temporarily locked after too many failed sign in attempts".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+async fn record_failed_login(repo: &UserRepository, user: &User) -> Result<(), Error> {
+    let lock_until = Utc::now().naive_utc() + chrono::Duration::minutes(LOGIN_SETTINGS.lockout_minutes);
+    repo.record_failed_login(user.id, LOGIN_SETTINGS.max_failed_attempts, lock_until)
+        .await?;
+    Ok(())
+}
 
 pub async  fn token(
     pool: web::Data<DbPool>,
+    senders: web::Data<OtpSenders>,
+    limiter: web::Data<RateLimiter>,
+    client: &str,
     payload: web::Json<LoginRequest>,
-)-> Result<HashMap<String, String>, AppError> {
-    let repo = UserRepository::new(pool);
-    let user = repo.find_by_username(&payload.username).await;
+)-> Result<LoginOutcome, Error> {
+    throttle(&limiter, client, &payload.identifier)?;
+    let repo = UserRepository::new(pool.clone());
+    let user = repo.find_by_login(&payload.identifier).await;
     match user {
-        Ok(user) => {
-            let hasesd = String::from_utf8(user.1.clone().unwrap().password_hash.expect("Error")).expect("Error");
+        Ok((user, Some(PasswordHash { password_hash: Some(hash), .. }))) => {
+            ensure_not_locked(&user)?;
+            let hasesd = String::from_utf8(hash).map_err(|e| AppError::InternalError(e.to_string()))?;
             let password = Password{
                 plain: payload.password.clone(),
             };
             if !password.verify_password(&hasesd) {
-                Err(AppError::Unauthorized("Invalid username or password".to_string()))
-            }else{
-                let token = Claim {
-                    iss: "localhost".to_string(),
-                    jti: Uuid::new_v4().to_string(),
-                    aud: "audience".to_string(),
-                    nbf: Utc::now(),
-                    exp: Utc::now() + chrono::Duration::days(1),
-                    iat: Utc::now(),
-                    sub: user.0.id.to_string(),
-                };
-                let access_token = token.generate_token();
-                let refresh_token = token.generate_token();
-                let expires = Utc::now().timestamp() + 60 * 60 * 24 * 7; // 7 days
-                let mut res =  HashMap::new();
-                res.insert("access_token".to_string(), access_token);
-                res.insert("refresh_token".to_string(), refresh_token);
-                res.insert("expires".to_string(), expires.to_string());
-                res.insert("token_type".to_string(), "Bearer".to_string());
-                Ok(res)
+                record_failed_login(&repo, &user).await?;
+                return Err(AppError::Unauthorized("Invalid username or password".to_string()).into());
             }
+            // With a second factor the sign in only succeeds once that is
+            // checked too, so the failed attempts stay counted until then.
+            if user.two_factor_method.is_otp_delivery() {
+                let challenge = otp_services::issue_otp(
+                    pool,
+                    &senders,
+                    &user,
+                    user.two_factor_method,
+                    OtpPurpose::Login,
+                )
+                .await?;
+                Ok(LoginOutcome::MfaRequired(challenge))
+            } else if user.two_factor_method == TwoFactorMethodEnum::Webauthn {
+                let ceremony = webauthn_services::start_authentication_for(pool, &user, true).await?;
+                Ok(LoginOutcome::WebauthnRequired(ceremony))
+            } else {
+                let user = repo.record_successful_login(user.id).await?;
+                Ok(LoginOutcome::Tokens(issue_tokens(pool, &user, &[AMR_PASSWORD]).await?))
+            }
+        }
+        // An outage is not a wrong password.
+        Err(e) if matches!(e.as_error::<AppError>(), Some(AppError::ServiceUnavailable(_))) => Err(e),
+        _ => Err(AppError::Unauthorized("Invalid username or password".to_string()).into()),
+    }
+}
+
+/// Completes a login that was paused for a second factor. Wrong codes count
+/// toward the account lockout like wrong passwords do.
+pub async fn verify_mfa(
+    pool: web::Data<DbPool>,
+    challenge_id: Uuid,
+    code: &str,
+) -> Result<HashMap<String, String>, Error> {
+    let invalid = || AppError::Unauthorized("Invalid or expired verification code".to_string());
+    let pending = OtpRepository::new(pool.clone()).find_by_id(challenge_id).await.map_err(|_| invalid())?;
+    if pending.purpose != OtpPurpose::Login.as_str() {
+        return Err(invalid().into());
+    }
+    let repo = UserRepository::new(pool.clone());
+    let user = repo.find_by_id(pending.user_id).await?;
+    ensure_not_locked(&user)?;
+    if let Err(e) = otp_services::verify_otp(pool.clone(), challenge_id, code, OtpPurpose::Login).await {
+        if matches!(e.as_error::<AppError>(), Some(AppError::Unauthorized(_))) {
+            record_failed_login(&repo, &user).await?;
         }
-        Err(e) =>Err(AppError::NotFound("User not found".to_string())),
-        _ => Err(AppError::Unauthorized("Invalid username or password".to_string())),
+        return Err(e);
     }
-    // This function will be implemented in the next step
+    let user = repo.record_successful_login(user.id).await?;
+    issue_tokens(pool, &user, &[AMR_PASSWORD, AMR_OTP]).await
 }
 
+/// Emails a single-use sign in link. The returned nonce binds the link to the
+/// requesting device; unknown addresses get the same response so the endpoint
+/// cannot be used to discover accounts.
+pub async fn request_magic_link(
+    pool: web::Data<DbPool>,
+    senders: web::Data<OtpSenders>,
+    limiter: web::Data<RateLimiter>,
+    client: &str,
+    email: &str,
+) -> Result<MagicLinkIssued, Error> {
+    throttle(&limiter, client, email)?;
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::minutes(MAGIC_LINK_SETTINGS.ttl_minutes);
+    let device_nonce = Uuid::new_v4().simple().to_string();
+    let issued = MagicLinkIssued {
+        device_nonce: device_nonce.clone(),
+        expires_at: expires_at.naive_utc(),
+    };
+
+    let user = match UserRepository::new(pool.clone()).find_by_email(email).await {
+        Ok(user) if user.is_active => user,
+        _ => return Ok(issued),
+    };
+    if ensure_not_locked(&user).is_err() {
+        return Ok(issued);
+    }
+
+    let id = Uuid::new_v4();
+    MagicLinkRepository::new(pool)
+        .create(NewMagicLink {
+            id,
+            user_id: user.id,
+            nonce_hash: hash_code(&id, &device_nonce),
+            expires_at: issued.expires_at,
+        })
+        .await?;
+
+    let token = Claim {
+        iss: "localhost".to_string(),
+        jti: id.to_string(),
+        aud: MAGIC_LINK_AUDIENCE.to_string(),
+        nbf: now,
+        exp: expires_at,
+        iat: now,
+        sub: user.id.to_string(),
+        roles: None,
+        permissions: None,
+        amr: None,
+    }
+    .generate_token();
+    senders
+        .get(TwoFactorMethodEnum::Email)?
+        .send(&OtpMessage {
+            recipient: user.email,
+            code: format!("{}?token={}", MAGIC_LINK_SETTINGS.base_url, token),
+            purpose: OtpPurpose::MagicLink,
+            expires_in_minutes: MAGIC_LINK_SETTINGS.ttl_minutes,
+        })
+        .await?;
+    Ok(issued)
+}
+
+/// Redeems a magic link from the device that requested it and issues tokens.
+pub async fn consume_magic_link(
+    pool: web::Data<DbPool>,
+    limiter: web::Data<RateLimiter>,
+    client: &str,
+    token: &str,
+    device_nonce: &str,
+) -> Result<HashMap<String, String>, Error> {
+    let invalid = || AppError::Unauthorized("Invalid or expired sign in link".to_string());
+    let claims = decrypt_token(token).map_err(|_| invalid())?;
+    let claim = |name: &str| claims.get_claim(name).and_then(|v| v.as_str()).map(str::to_string);
+    if claim("aud").as_deref() != Some(MAGIC_LINK_AUDIENCE) {
+        return Err(invalid().into());
+    }
+    let id = claim("jti").and_then(|v| Uuid::parse_str(&v).ok()).ok_or_else(invalid)?;
+    let user_id = claim("sub").and_then(|v| Uuid::parse_str(&v).ok()).ok_or_else(invalid)?;
+    limiter.check(&format!("ip:{}", client))?;
+
+    let links = MagicLinkRepository::new(pool.clone());
+    let link = links.find_by_id(id).await.map_err(|_| invalid())?;
+    if link.user_id != user_id || link.consumed_at.is_some() || link.expires_at < Utc::now().naive_utc() {
+        return Err(invalid().into());
+    }
+
+    let repo = UserRepository::new(pool.clone());
+    let user = repo.find_by_id(user_id).await?;
+    ensure_not_locked(&user)?;
+    if !verify_code(&link.id, device_nonce, &link.nonce_hash) {
+        record_failed_login(&repo, &user).await?;
+        return Err(AppError::Unauthorized(
+            "This link must be opened on the device that requested it".to_string(),
+        )
+        .into());
+    }
+    if !links.consume(link.id).await? {
+        return Err(invalid().into());
+    }
+    let user = repo.record_successful_login(user.id).await?;
+    issue_tokens(pool, &user, &[AMR_MAGIC_LINK]).await
+}
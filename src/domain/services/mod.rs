@@ -1,2 +1,8 @@
 pub mod user_services;
-pub mod authentication;
\ No newline at end of file
+pub mod authentication;
+pub mod otp_services;
+pub mod webauthn_services;
+pub mod permission_services;
+pub mod rbac_services;
+pub mod policy_services;
+pub(crate) mod role_hierarchy;
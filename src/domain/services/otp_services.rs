@@ -0,0 +1,104 @@
+use actix_web::{web, Error};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::config::otp::OTP_SETTINGS;
+use crate::domain::models::otp::{NewOtpCode, OtpChallenge, OtpCode, OtpPurpose};
+use crate::domain::models::user::{TwoFactorMethodEnum, User};
+use crate::domain::repositories::otp_repository::OtpRepository;
+use crate::infrastructure::external::otp_sender::{OtpMessage, OtpSenders};
+use crate::utils::errors::AppError;
+use crate::utils::otp::{generate_code, hash_code, verify_code};
+
+/// Resolves where a code for `method` should be delivered for this user.
+fn recipient_for(user: &User, method: TwoFactorMethodEnum) -> Result<String, AppError> {
+    match method {
+        TwoFactorMethodEnum::Email => Ok(user.email.clone()),
+        TwoFactorMethodEnum::Sms | TwoFactorMethodEnum::Whatsapp => user
+            .phone_number
+            .clone()
+            .ok_or_else(|| AppError::BadRequest("No phone number on this account".to_string())),
+        _ => Err(AppError::BadRequest(format!("{:?} does not use delivered codes", method))),
+    }
+}
+
+/// Generates a code, stores its hash and delivers it over `method`.
+///
+/// Any earlier code for the same purpose is invalidated first.
+pub(crate) async fn issue_otp(
+    pool: web::Data<DbPool>,
+    senders: &OtpSenders,
+    user: &User,
+    method: TwoFactorMethodEnum,
+    purpose: OtpPurpose,
+) -> Result<OtpChallenge, Error> {
+    let recipient = recipient_for(user, method)?;
+    let sender = senders.get(method)?;
+    let repo = OtpRepository::new(pool);
+
+    repo.invalidate_pending(user.id, purpose.as_str()).await?;
+
+    let challenge_id = Uuid::new_v4();
+    let code = generate_code(OTP_SETTINGS.length);
+    let expires_at = Utc::now().naive_utc() + Duration::minutes(OTP_SETTINGS.ttl_minutes);
+    let stored = repo
+        .create(NewOtpCode {
+            id: challenge_id,
+            user_id: user.id,
+            method,
+            purpose: purpose.as_str().to_string(),
+            recipient: recipient.clone(),
+            code_hash: hash_code(&challenge_id, &code),
+            max_attempts: OTP_SETTINGS.max_attempts,
+            expires_at,
+        })
+        .await?;
+
+    sender
+        .send(&OtpMessage {
+            recipient,
+            code,
+            purpose,
+            expires_in_minutes: OTP_SETTINGS.ttl_minutes,
+        })
+        .await?;
+
+    Ok(OtpChallenge {
+        mfa_required: true,
+        challenge_id: stored.id,
+        method,
+        expires_at: stored.expires_at,
+    })
+}
+
+/// Redeems a code. Succeeds at most once per challenge, and only before it
+/// expires or runs out of attempts.
+pub(crate) async fn verify_otp(
+    pool: web::Data<DbPool>,
+    challenge_id: Uuid,
+    code: &str,
+    purpose: OtpPurpose,
+) -> Result<OtpCode, Error> {
+    let invalid = || AppError::Unauthorized("Invalid or expired verification code".to_string());
+    let repo = OtpRepository::new(pool);
+
+    let otp = repo.find_by_id(challenge_id).await.map_err(|_| invalid())?;
+    if otp.purpose != purpose.as_str() || otp.consumed_at.is_some() {
+        return Err(invalid().into());
+    }
+    if otp.expires_at < Utc::now().naive_utc() {
+        return Err(AppError::Unauthorized("Verification code expired".to_string()).into());
+    }
+    let otp = repo
+        .register_attempt(challenge_id)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Too many attempts, request a new code".to_string()))?;
+
+    if !verify_code(&otp.id, code.trim(), &otp.code_hash) {
+        return Err(invalid().into());
+    }
+    if !repo.consume(otp.id).await? {
+        return Err(invalid().into());
+    }
+    Ok(otp)
+}
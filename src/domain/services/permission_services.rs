@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use actix_web::{web, Error};
+use once_cell::sync::Lazy;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::config::security::AUTHORIZATION_SETTINGS;
+use crate::domain::models::rbac::{
+    AccessGrants, EffectivePermission, GrantPath, PermissionAction, PermissionExplanation, Role, RolePermission,
+};
+use crate::domain::repositories::permission_repository::PermissionRepository;
+use crate::domain::repositories::role_repository::RoleRepository;
+use crate::domain::services::role_hierarchy::RoleGraph;
+use crate::utils::cache::TtlCache;
+use crate::utils::errors::AppError;
+
+/// Resolves what an account may do from the roles assigned to it and the
+/// roles those inherit from.
+pub struct PermissionService {
+    pool: web::Data<DbPool>,
+}
+
+static ACCESS_CACHE: Lazy<TtlCache<Uuid, Arc<AccessGrants>>> = Lazy::new(|| {
+    TtlCache::new(Duration::from_secs(AUTHORIZATION_SETTINGS.cache_ttl_seconds))
+});
+
+pub(crate) fn missing_permission(permission: &str, action: PermissionAction) -> AppError {
+    AppError::Forbidden(format!("Missing {} permission on {}", action.as_str(), permission))
+}
+
+/// Folds per-role grants into one entry per permission name.
+pub(crate) fn merge_grants(grants: Vec<(String, RolePermission)>) -> Vec<EffectivePermission> {
+    let mut merged: BTreeMap<String, EffectivePermission> = BTreeMap::new();
+    for (name, grant) in grants {
+        merged
+            .entry(name.clone())
+            .or_insert_with(|| EffectivePermission { name, ..Default::default() })
+            .merge(&grant);
+    }
+    merged.into_values().collect()
+}
+
+/// Roles reachable from an account, each with the chain leading to it.
+struct ResolvedRoles {
+    assigned: Vec<Role>,
+    reachable: Vec<(Uuid, Vec<Uuid>)>,
+}
+
+impl PermissionService {
+    pub fn new(pool: web::Data<DbPool>) -> Self {
+        PermissionService { pool }
+    }
+
+    async fn resolve_roles(&self, account_id: Uuid) -> Result<ResolvedRoles, Error> {
+        let roles = RoleRepository::new(self.pool.clone());
+        let assigned = roles.find_by_account(account_id).await?;
+        if assigned.is_empty() {
+            return Ok(ResolvedRoles { assigned, reachable: vec![] });
+        }
+        let graph = RoleGraph::new(
+            roles
+                .find_parent_links()
+                .await?
+                .into_iter()
+                .map(|link| (link.role_id, link.parent_id)),
+        );
+        let assigned_ids: Vec<Uuid> = assigned.iter().map(|role| role.id).collect();
+        let reachable = graph.resolve(&assigned_ids);
+        Ok(ResolvedRoles { assigned, reachable })
+    }
+
+    async fn grants_for(&self, role_ids: Vec<Uuid>) -> Result<Vec<(String, RolePermission)>, Error> {
+        if role_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        PermissionRepository::new(self.pool.clone())
+            .grants_for_roles(role_ids)
+            .await
+    }
+
+    /// Role names and merged grants of the account, inherited ones included.
+    /// Served from the in-process cache when possible.
+    pub async fn access(&self, account_id: Uuid) -> Result<Arc<AccessGrants>, Error> {
+        if let Some(access) = ACCESS_CACHE.get(&account_id) {
+            return Ok(access);
+        }
+        let generation = ACCESS_CACHE.generation();
+        let resolved = self.resolve_roles(account_id).await?;
+        let role_ids: Vec<Uuid> = resolved.reachable.iter().map(|(id, _)| *id).collect();
+        let roles = if role_ids.len() == resolved.assigned.len() {
+            resolved.assigned.into_iter().map(|role| role.name).collect()
+        } else {
+            let names: HashMap<Uuid, String> = RoleRepository::new(self.pool.clone())
+                .find_all()
+                .await?
+                .into_iter()
+                .map(|role| (role.id, role.name))
+                .collect();
+            role_ids.iter().filter_map(|id| names.get(id).cloned()).collect()
+        };
+        let access = Arc::new(AccessGrants {
+            roles,
+            permissions: merge_grants(self.grants_for(role_ids).await?),
+        });
+        ACCESS_CACHE.insert(account_id, access.clone(), generation);
+        Ok(access)
+    }
+
+    /// Grants of the account's roles and of every role they inherit from.
+    pub async fn effective_permissions(&self, account_id: Uuid) -> Result<Vec<EffectivePermission>, Error> {
+        Ok(self.access(account_id).await?.permissions.clone())
+    }
+
+    pub async fn has_permission(
+        &self,
+        account_id: Uuid,
+        permission: &str,
+        action: PermissionAction,
+    ) -> Result<bool, Error> {
+        Ok(self.access(account_id).await?.allows(permission, action))
+    }
+
+    /// Drops the cached grants of one account, e.g. after its roles changed.
+    pub fn invalidate_account(account_id: Uuid) {
+        ACCESS_CACHE.invalidate(&account_id);
+    }
+
+    /// Drops every cached grant; used when roles or permissions themselves change.
+    pub fn invalidate_all() {
+        ACCESS_CACHE.clear();
+    }
+
+    /// Fails with `Forbidden` unless the account holds `action` on `permission`.
+    pub async fn require(
+        &self,
+        account_id: Uuid,
+        permission: &str,
+        action: PermissionAction,
+    ) -> Result<(), Error> {
+        if self.has_permission(account_id, permission, action).await? {
+            Ok(())
+        } else {
+            Err(missing_permission(permission, action).into())
+        }
+    }
+
+    /// Lists the role chains through which the account holds `action` on
+    /// `permission`; an empty list means it does not.
+    pub async fn explain(
+        &self,
+        account_id: Uuid,
+        permission: &str,
+        action: PermissionAction,
+    ) -> Result<PermissionExplanation, Error> {
+        let resolved = self.resolve_roles(account_id).await?;
+        let role_ids = resolved.reachable.iter().map(|(id, _)| *id).collect();
+        let granting: HashSet<Uuid> = self
+            .grants_for(role_ids)
+            .await?
+            .into_iter()
+            .filter(|(name, grant)| {
+                name == permission && EffectivePermission::from_grant(name, grant).allows(action)
+            })
+            .map(|(_, grant)| grant.role_id)
+            .collect();
+
+        let names: HashMap<Uuid, String> = RoleRepository::new(self.pool.clone())
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|role| (role.id, role.name))
+            .collect();
+        let name_of = |id: &Uuid| names.get(id).cloned().unwrap_or_else(|| id.to_string());
+        let paths: Vec<GrantPath> = resolved
+            .reachable
+            .iter()
+            .filter(|(id, _)| granting.contains(id))
+            .map(|(_, path)| GrantPath { roles: path.iter().map(name_of).collect() })
+            .collect();
+
+        Ok(PermissionExplanation {
+            account_id,
+            permission: permission.to_string(),
+            action,
+            allowed: !paths.is_empty(),
+            assigned_roles: resolved.assigned.into_iter().map(|role| role.name).collect(),
+            paths,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(read: bool, write: bool, update: bool, delete: bool) -> RolePermission {
+        RolePermission {
+            role_id: Uuid::new_v4(),
+            permission_id: Uuid::new_v4(),
+            read,
+            write,
+            update,
+            delete,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn grants_from_several_roles_are_combined() {
+        let merged = merge_grants(vec![
+            ("users".to_string(), grant(true, false, false, false)),
+            ("users".to_string(), grant(false, false, true, false)),
+            ("roles".to_string(), grant(true, false, false, false)),
+        ]);
+        assert_eq!(merged.len(), 2);
+        let users = merged.iter().find(|p| p.name == "users").unwrap();
+        assert!(users.allows(PermissionAction::Read));
+        assert!(users.allows(PermissionAction::Update));
+        assert!(!users.allows(PermissionAction::Write));
+        assert!(!users.allows(PermissionAction::Delete));
+    }
+
+    #[test]
+    fn compact_form_round_trips() {
+        let users = EffectivePermission::from_grant("users", &grant(true, false, true, false));
+        assert_eq!(users.to_compact(), "users:ru");
+        assert_eq!(EffectivePermission::from_compact("users:ru"), Some(users));
+        assert_eq!(EffectivePermission::from_compact("users:"), Some(EffectivePermission {
+            name: "users".to_string(),
+            ..Default::default()
+        }));
+        assert_eq!(EffectivePermission::from_compact("users:rx"), None);
+        assert_eq!(EffectivePermission::from_compact("users"), None);
+    }
+
+    #[test]
+    fn no_grants_means_no_permissions() {
+        assert!(merge_grants(vec![]).is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use actix_web::{web, Error};
+use serde_json::Value;
+use uuid::Uuid;
+use crate::config::database::DbPool;
+use crate::domain::models::policy::{
+    Condition, Effect, Operand, Policy, PolicyDecision, PolicyRequest, PolicyResource, PolicySet,
+    PolicySubject, PolicyTrace,
+};
+use crate::domain::repositories::user_repository::UserRepository;
+use crate::domain::repository::Repository;
+use crate::domain::services::permission_services::PermissionService;
+use crate::utils::errors::AppError;
+
+/// Evaluates declarative policies. A matching `deny` always wins, otherwise a
+/// matching `allow` grants access; when nothing matches the request is denied.
+pub struct PolicyEngine {
+    policies: PolicySet,
+}
+
+fn matches(patterns: &[String], value: &str) -> bool {
+    patterns.iter().any(|pattern| pattern == "*" || pattern == value)
+}
+
+/// Looks up a dotted path such as `subject.attributes.status` in the request.
+fn lookup<'a>(document: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(document, |value, key| value.get(key))
+}
+
+fn resolve<'a>(document: &'a Value, operand: &'a Operand) -> Option<&'a Value> {
+    match operand {
+        Operand::Attribute { attr } => lookup(document, attr),
+        Operand::Literal(value) => Some(value),
+    }
+}
+
+fn holds(condition: &Condition, request: &PolicyRequest, document: &Value) -> bool {
+    match condition {
+        Condition::All(conditions) => conditions.iter().all(|c| holds(c, request, document)),
+        Condition::Any(conditions) => conditions.iter().any(|c| holds(c, request, document)),
+        Condition::Not(condition) => !holds(condition, request, document),
+        Condition::Equals(left, right) => match (resolve(document, left), resolve(document, right)) {
+            (Some(left), Some(right)) => !left.is_null() && left == right,
+            _ => false,
+        },
+        Condition::In(needle, haystack) => match (resolve(document, needle), resolve(document, haystack)) {
+            (Some(needle), Some(Value::Array(items))) => items.contains(needle),
+            _ => false,
+        },
+        Condition::HasRole(role) => request.subject.roles.iter().any(|r| r == role),
+        Condition::HasPermission { permission, action } => request
+            .subject
+            .permissions
+            .iter()
+            .any(|p| &p.name == permission && p.allows(*action)),
+    }
+}
+
+impl PolicyEngine {
+    pub fn new(policies: PolicySet) -> Self {
+        PolicyEngine { policies }
+    }
+
+    pub fn policies(&self) -> &[Policy] {
+        &self.policies.policies
+    }
+
+    pub fn evaluate(&self, request: &PolicyRequest) -> PolicyDecision {
+        let document = serde_json::to_value(request).unwrap_or(Value::Null);
+        let trace: Vec<PolicyTrace> = self
+            .policies()
+            .iter()
+            .map(|policy| {
+                let applicable = matches(&policy.actions, &request.action)
+                    && matches(&policy.resources, &request.resource.kind);
+                let matched = applicable
+                    && policy
+                        .condition
+                        .as_ref()
+                        .is_none_or(|condition| holds(condition, request, &document));
+                PolicyTrace { policy: policy.id.clone(), effect: policy.effect, applicable, matched }
+            })
+            .collect();
+
+        let first_match = |effect: Effect| {
+            trace
+                .iter()
+                .find(|t| t.matched && t.effect == effect)
+                .map(|t| t.policy.clone())
+        };
+        let (allowed, policy, reason) = if let Some(policy) = first_match(Effect::Deny) {
+            (false, Some(policy), "Denied by policy".to_string())
+        } else if let Some(policy) = first_match(Effect::Allow) {
+            (true, Some(policy), "Allowed by policy".to_string())
+        } else {
+            (false, None, "No policy allows this request".to_string())
+        };
+        PolicyDecision { allowed, policy, reason, trace }
+    }
+}
+
+/// Builds the subject for an account from its record and resolved grants.
+pub(crate) async fn subject_for(pool: web::Data<DbPool>, account_id: Uuid) -> Result<PolicySubject, Error> {
+    let account = UserRepository::new(pool.clone()).find_by_id(account_id).await?;
+    let access = PermissionService::new(pool).access(account_id).await?;
+    Ok(PolicySubject {
+        id: account_id,
+        roles: access.roles.clone(),
+        permissions: access.permissions.clone(),
+        attributes: serde_json::to_value(&account).unwrap_or(Value::Null),
+    })
+}
+
+/// Evaluates the policies for an account acting on a resource. Accounts used
+/// as resources get their record filled in as attributes unless provided.
+pub(crate) async fn evaluate(
+    pool: web::Data<DbPool>,
+    engine: &PolicyEngine,
+    account_id: Uuid,
+    action: &str,
+    mut resource: PolicyResource,
+) -> Result<PolicyDecision, Error> {
+    if resource.kind == "account" && resource.attributes.is_null() {
+        let target = resource.id.as_deref().and_then(|id| Uuid::parse_str(id).ok());
+        if let Some(target) = target {
+            if let Ok(account) = UserRepository::new(pool.clone()).find_by_id(target).await {
+                resource.attributes = serde_json::to_value(&account).unwrap_or(Value::Null);
+            }
+        }
+    }
+    let request = PolicyRequest {
+        subject: subject_for(pool, account_id).await?,
+        action: action.to_string(),
+        resource,
+    };
+    Ok(engine.evaluate(&request))
+}
+
+/// Fails with `Forbidden` unless the policies allow the request.
+pub(crate) async fn authorize(
+    pool: web::Data<DbPool>,
+    engine: &PolicyEngine,
+    account_id: Uuid,
+    action: &str,
+    resource: PolicyResource,
+) -> Result<(), Error> {
+    let kind = resource.kind.clone();
+    let decision = evaluate(pool, engine, account_id, action, resource).await?;
+    match (decision.allowed, decision.policy) {
+        (true, _) => Ok(()),
+        (false, Some(policy)) => Err(AppError::Forbidden(format!("Denied by policy {}", policy)).into()),
+        (false, None) => Err(AppError::Forbidden(format!("Not allowed to {} this {}", action, kind)).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::rbac::EffectivePermission;
+
+    fn engine() -> PolicyEngine {
+        PolicyEngine::new(serde_json::from_str(include_str!("../../../policies.json")).unwrap())
+    }
+
+    fn subject(id: Uuid, permissions: &[&str], status: &str) -> PolicySubject {
+        PolicySubject {
+            id,
+            roles: vec![],
+            permissions: permissions
+                .iter()
+                .map(|p| EffectivePermission::from_compact(p).unwrap())
+                .collect(),
+            attributes: serde_json::json!({ "status": status }),
+        }
+    }
+
+    fn request(subject: PolicySubject, action: &str, target: Uuid) -> PolicyRequest {
+        PolicyRequest {
+            subject,
+            action: action.to_string(),
+            resource: PolicyResource {
+                kind: "account".to_string(),
+                id: Some(target.to_string()),
+                attributes: Value::Null,
+            },
+        }
+    }
+
+    #[test]
+    fn owners_may_update_their_own_account() {
+        let id = Uuid::new_v4();
+        let decision = engine().evaluate(&request(subject(id, &[], "Active"), "update", id));
+        assert!(decision.allowed);
+        assert_eq!(decision.policy.as_deref(), Some("owner-manages-own-account"));
+    }
+
+    #[test]
+    fn others_are_denied_without_a_permission() {
+        let decision =
+            engine().evaluate(&request(subject(Uuid::new_v4(), &["users:r"], "Active"), "update", Uuid::new_v4()));
+        assert!(!decision.allowed);
+        assert_eq!(decision.policy, None);
+    }
+
+    #[test]
+    fn user_admins_may_update_any_account() {
+        let decision =
+            engine().evaluate(&request(subject(Uuid::new_v4(), &["users:ru"], "Active"), "update", Uuid::new_v4()));
+        assert!(decision.allowed);
+        assert_eq!(decision.policy.as_deref(), Some("user-admins-manage-accounts"));
+    }
+
+    #[test]
+    fn deny_overrides_allow() {
+        let id = Uuid::new_v4();
+        let decision = engine().evaluate(&request(subject(id, &["users:rwud"], "Suspended"), "delete", id));
+        assert!(!decision.allowed);
+        assert_eq!(decision.policy.as_deref(), Some("suspended-accounts-change-nothing"));
+        assert!(decision.trace.iter().filter(|t| t.matched).count() >= 2);
+    }
+}
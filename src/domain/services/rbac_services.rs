@@ -0,0 +1,235 @@
+use actix_web::{web, Error};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use uuid::Uuid;
+use crate::api::dto::requests::rbac::GrantRequest;
+use crate::config::database::DbPool;
+use crate::domain::models::rbac::{
+    NewPermission, NewRole, NewRolePermission, Permission, PermissionAction, PermissionExplanation,
+    Role, RolePermission, RoleWithPermissions,
+};
+use crate::domain::repositories::permission_repository::PermissionRepository;
+use crate::domain::repositories::role_repository::RoleRepository;
+use crate::domain::repositories::user_repository::UserRepository;
+use crate::domain::repository::Repository;
+use crate::domain::services::permission_services::{merge_grants, PermissionService};
+use crate::domain::services::role_hierarchy::RoleGraph;
+use crate::infrastructure::database::unit_of_work::UnitOfWork;
+use crate::utils::errors::AppError;
+
+fn parse_id(id: &str, what: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(id).map_err(|_| AppError::BadRequest(format!("Invalid {} id", what)))
+}
+
+fn validate_name(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::ValidationError("Name must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+pub(crate) async fn list_roles(pool: web::Data<DbPool>) -> Result<Vec<Role>, Error> {
+    RoleRepository::new(pool).find_all().await
+}
+
+pub(crate) async fn get_role(pool: web::Data<DbPool>, id: String) -> Result<RoleWithPermissions, Error> {
+    let id = parse_id(&id, "role")?;
+    let repo = RoleRepository::new(pool.clone());
+    let role = repo.find_by_id(id).await?;
+    let links = repo.find_parent_links().await?;
+    let parents = links
+        .iter()
+        .filter(|link| link.role_id == id)
+        .map(|link| link.parent_id)
+        .collect();
+    let inherited = RoleGraph::new(links.into_iter().map(|link| (link.role_id, link.parent_id)))
+        .resolve(&[id])
+        .into_iter()
+        .map(|(role_id, _)| role_id)
+        .collect();
+    let permissions = merge_grants(repo.find_grants(id).await?);
+    let effective_permissions =
+        merge_grants(PermissionRepository::new(pool).grants_for_roles(inherited).await?);
+    Ok(RoleWithPermissions { role, parents, permissions, effective_permissions })
+}
+
+/// Makes `id` inherit from `parent_id`, refusing links that would form a cycle.
+pub(crate) async fn add_parent(
+    pool: web::Data<DbPool>,
+    id: String,
+    parent_id: String,
+) -> Result<RoleWithPermissions, Error> {
+    let role_id = parse_id(&id, "role")?;
+    let parent = parse_id(&parent_id, "parent role")?;
+    // The check and the insert share one transaction that holds the
+    // hierarchy lock, so two opposite links added at once cannot both pass.
+    UnitOfWork::new(pool.clone())
+        .run(move |conn| {
+            async move {
+                RoleRepository::lock_role(conn, role_id).await?;
+                RoleRepository::lock_role(conn, parent).await?;
+                let links = RoleRepository::lock_hierarchy(conn).await?;
+                let graph = RoleGraph::new(links.into_iter().map(|link| (link.role_id, link.parent_id)));
+                if graph.creates_cycle(role_id, parent) {
+                    return Err(AppError::ValidationError(
+                        "Inheriting from this role would create a cycle".to_string(),
+                    ));
+                }
+                RoleRepository::insert_parent(conn, role_id, parent).await
+            }
+            .scope_boxed()
+        })
+        .await?;
+    PermissionService::invalidate_all();
+    get_role(pool, id).await
+}
+
+pub(crate) async fn remove_parent(
+    pool: web::Data<DbPool>,
+    id: String,
+    parent_id: String,
+) -> Result<RoleWithPermissions, Error> {
+    let role_id = parse_id(&id, "role")?;
+    let parent = parse_id(&parent_id, "parent role")?;
+    RoleRepository::new(pool.clone()).remove_parent(role_id, parent).await?;
+    PermissionService::invalidate_all();
+    get_role(pool, id).await
+}
+
+pub(crate) async fn create_role(pool: web::Data<DbPool>, role: NewRole) -> Result<Role, Error> {
+    validate_name(&role.name)?;
+    RoleRepository::new(pool).create(role).await
+}
+
+pub(crate) async fn update_role(pool: web::Data<DbPool>, id: String, role: NewRole) -> Result<Role, Error> {
+    let id = parse_id(&id, "role")?;
+    validate_name(&role.name)?;
+    let role = RoleRepository::new(pool).update(id, role).await?;
+    PermissionService::invalidate_all();
+    Ok(role)
+}
+
+pub(crate) async fn delete_role(pool: web::Data<DbPool>, id: String) -> Result<(), Error> {
+    let id = parse_id(&id, "role")?;
+    RoleRepository::new(pool).delete(id).await?;
+    PermissionService::invalidate_all();
+    Ok(())
+}
+
+pub(crate) async fn list_permissions(pool: web::Data<DbPool>) -> Result<Vec<Permission>, Error> {
+    PermissionRepository::new(pool).find_all().await
+}
+
+pub(crate) async fn create_permission(
+    pool: web::Data<DbPool>,
+    permission: NewPermission,
+) -> Result<Permission, Error> {
+    validate_name(&permission.name)?;
+    PermissionRepository::new(pool).create(permission).await
+}
+
+pub(crate) async fn update_permission(
+    pool: web::Data<DbPool>,
+    id: String,
+    permission: NewPermission,
+) -> Result<Permission, Error> {
+    let id = parse_id(&id, "permission")?;
+    validate_name(&permission.name)?;
+    let permission = PermissionRepository::new(pool).update(id, permission).await?;
+    PermissionService::invalidate_all();
+    Ok(permission)
+}
+
+pub(crate) async fn delete_permission(pool: web::Data<DbPool>, id: String) -> Result<(), Error> {
+    let id = parse_id(&id, "permission")?;
+    PermissionRepository::new(pool).delete(id).await?;
+    PermissionService::invalidate_all();
+    Ok(())
+}
+
+/// Sets what a role may do on a permission, replacing any earlier flags.
+pub(crate) async fn grant_permission(
+    pool: web::Data<DbPool>,
+    role_id: String,
+    permission_id: String,
+    flags: GrantRequest,
+) -> Result<RolePermission, Error> {
+    let role_id = parse_id(&role_id, "role")?;
+    let permission_id = parse_id(&permission_id, "permission")?;
+    RoleRepository::new(pool.clone()).find_by_id(role_id).await?;
+    PermissionRepository::new(pool.clone()).find_by_id(permission_id).await?;
+    let grant = RoleRepository::new(pool)
+        .grant(NewRolePermission {
+            role_id,
+            permission_id,
+            read: flags.read,
+            write: flags.write,
+            update: flags.update,
+            delete: flags.delete,
+        })
+        .await?;
+    PermissionService::invalidate_all();
+    Ok(grant)
+}
+
+pub(crate) async fn revoke_permission(
+    pool: web::Data<DbPool>,
+    role_id: String,
+    permission_id: String,
+) -> Result<(), Error> {
+    let role_id = parse_id(&role_id, "role")?;
+    let permission_id = parse_id(&permission_id, "permission")?;
+    RoleRepository::new(pool).remove_grant(role_id, permission_id).await?;
+    PermissionService::invalidate_all();
+    Ok(())
+}
+
+pub(crate) async fn account_roles(pool: web::Data<DbPool>, account_id: String) -> Result<Vec<Role>, Error> {
+    let account_id = parse_id(&account_id, "account")?;
+    UserRepository::new(pool.clone()).find_by_id(account_id).await?;
+    RoleRepository::new(pool).find_by_account(account_id).await
+}
+
+pub(crate) async fn assign_role(
+    pool: web::Data<DbPool>,
+    account_id: String,
+    role_id: String,
+) -> Result<Vec<Role>, Error> {
+    let account_id = parse_id(&account_id, "account")?;
+    let role_id = parse_id(&role_id, "role")?;
+    UnitOfWork::new(pool.clone())
+        .run(move |conn| {
+            async move {
+                UserRepository::lock_account(conn, account_id).await?;
+                RoleRepository::lock_role(conn, role_id).await?;
+                RoleRepository::insert_assignment(conn, account_id, role_id).await
+            }
+            .scope_boxed()
+        })
+        .await?;
+    PermissionService::invalidate_account(account_id);
+    RoleRepository::new(pool).find_by_account(account_id).await
+}
+
+pub(crate) async fn unassign_role(
+    pool: web::Data<DbPool>,
+    account_id: String,
+    role_id: String,
+) -> Result<Vec<Role>, Error> {
+    let account_id = parse_id(&account_id, "account")?;
+    let role_id = parse_id(&role_id, "role")?;
+    let repo = RoleRepository::new(pool);
+    repo.revoke(account_id, role_id).await?;
+    PermissionService::invalidate_account(account_id);
+    repo.find_by_account(account_id).await
+}
+
+pub(crate) async fn explain_permission(
+    pool: web::Data<DbPool>,
+    account_id: String,
+    permission: String,
+    action: PermissionAction,
+) -> Result<PermissionExplanation, Error> {
+    let account_id = parse_id(&account_id, "account")?;
+    UserRepository::new(pool.clone()).find_by_id(account_id).await?;
+    PermissionService::new(pool).explain(account_id, &permission, action).await
+}
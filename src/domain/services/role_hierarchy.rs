@@ -0,0 +1,87 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use uuid::Uuid;
+
+/// Role inheritance edges, `role -> parents`.
+pub(crate) struct RoleGraph {
+    parents: HashMap<Uuid, Vec<Uuid>>,
+}
+
+impl RoleGraph {
+    pub fn new(edges: impl IntoIterator<Item = (Uuid, Uuid)>) -> Self {
+        let mut parents: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for (role, parent) in edges {
+            parents.entry(role).or_default().push(parent);
+        }
+        RoleGraph { parents }
+    }
+
+    /// Every role reachable from `assigned` together with the shortest chain
+    /// leading to it, starting at the assigned role. Each role is visited once,
+    /// so a cycle that slipped into the table cannot loop forever.
+    pub fn resolve(&self, assigned: &[Uuid]) -> Vec<(Uuid, Vec<Uuid>)> {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        let mut resolved = Vec::new();
+        for role in assigned {
+            if seen.insert(*role) {
+                queue.push_back((*role, vec![*role]));
+            }
+        }
+        while let Some((role, path)) = queue.pop_front() {
+            for parent in self.parents.get(&role).into_iter().flatten() {
+                if seen.insert(*parent) {
+                    let mut next = path.clone();
+                    next.push(*parent);
+                    queue.push_back((*parent, next));
+                }
+            }
+            resolved.push((role, path));
+        }
+        resolved
+    }
+
+    /// Whether making `role` inherit from `parent` would close a loop.
+    pub fn creates_cycle(&self, role: Uuid, parent: Uuid) -> bool {
+        role == parent || self.resolve(&[parent]).iter().any(|(id, _)| *id == role)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids() -> (Uuid, Uuid, Uuid) {
+        (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4())
+    }
+
+    #[test]
+    fn resolve_walks_the_chain_with_paths() {
+        let (admin, editor, viewer) = ids();
+        let graph = RoleGraph::new([(admin, editor), (editor, viewer)]);
+        let resolved = graph.resolve(&[admin]);
+        assert_eq!(
+            resolved,
+            vec![
+                (admin, vec![admin]),
+                (editor, vec![admin, editor]),
+                (viewer, vec![admin, editor, viewer]),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_terminates_on_cycles() {
+        let (a, b, c) = ids();
+        let graph = RoleGraph::new([(a, b), (b, c), (c, a)]);
+        assert_eq!(graph.resolve(&[a]).len(), 3);
+    }
+
+    #[test]
+    fn creates_cycle_detects_direct_and_transitive_loops() {
+        let (admin, editor, viewer) = ids();
+        let graph = RoleGraph::new([(admin, editor), (editor, viewer)]);
+        assert!(graph.creates_cycle(viewer, admin));
+        assert!(graph.creates_cycle(editor, editor));
+        assert!(!graph.creates_cycle(admin, viewer));
+    }
+}
@@ -1,53 +1,502 @@
 use actix_web::{web, Error};
 use actix_web::web::Data;
 use uuid::Uuid;
+use crate::api::dto::requests::user::{CreateUserRequest, UserListQuery, UserSearchQuery};
+use crate::api::dto::responses::{CursorPage, PageMeta, Paginated, UserSearchHit};
+use crate::config::accounts::RETENTION_SETTINGS;
 use crate::config::database::DbPool;
-use crate::domain::models::user::{NewUser, User};
+use crate::domain::models::otp::{OtpChallenge, OtpPurpose};
+use crate::domain::models::policy::PolicyResource;
+use crate::domain::models::rbac::PermissionAction;
+use crate::domain::models::user::{
+    AccountCursor, AccountStatusEnum, KeysetDirection, NewStatusChange, NewUser, SortOrder, StatusAction,
+    StatusChange, TwoFactorMethodEnum, User, UserChangeset, UserFilter, UserSortField,
+};
 use crate::domain::repository::Repository;
 use crate::domain::repositories::user_repository::UserRepository;
+use crate::domain::services::permission_services::PermissionService;
+use crate::domain::services::policy_services::{self, PolicyEngine};
+use crate::domain::services::{authentication, otp_services, webauthn_services};
+use crate::infrastructure::external::otp_sender::OtpSenders;
+use crate::utils::crypto::Password;
+use crate::utils::cursor;
+use crate::utils::errors::AppError;
+use crate::utils::phone::normalize_phone_number;
+
+fn normalize_user_phone(user: &mut NewUser) -> Result<(), AppError> {
+    user.phone_number = match user.phone_number.as_deref().map(str::trim) {
+        Some("") | None => None,
+        Some(raw) => Some(normalize_phone_number(raw)?),
+    };
+    Ok(())
+}
+
+fn phone_method_requires_verified_phone() -> AppError {
+    AppError::ValidationError(
+        "SMS and WhatsApp verification can only be enabled on a verified phone number".to_string(),
+    )
+}
+
+fn webauthn_requires_credential() -> AppError {
+    AppError::ValidationError("Register a security key before enabling WebAuthn verification".to_string())
+}
 
 pub(crate) async fn create_user(
     pool: web::Data<DbPool>,
-    user: NewUser) -> Result<User, Error> {
-    let user = UserRepository::new(pool).create(user).await?;
+    request: CreateUserRequest) -> Result<User, Error> {
+    if request.password.is_empty() {
+        return Err(AppError::ValidationError("Password must not be empty".to_string()).into());
+    }
+    let now = chrono::Utc::now().naive_utc();
+    let mut user = NewUser {
+        username: request.username,
+        email: request.email,
+        phone_number: request.phone_number,
+        is_active: true,
+        is_verified: false,
+        registration_date: now,
+        last_login: None,
+        two_factor_method: request.two_factor_method.unwrap_or(TwoFactorMethodEnum::None),
+        preferred_language: request.preferred_language,
+        status: AccountStatusEnum::Active,
+    };
+    normalize_user_phone(&mut user)?;
+    if user.two_factor_method.uses_phone() {
+        return Err(phone_method_requires_verified_phone().into());
+    }
+    if user.two_factor_method == TwoFactorMethodEnum::Webauthn {
+        return Err(webauthn_requires_credential().into());
+    }
+    let password = Password { plain: request.password };
+    let user = UserRepository::new(pool).create_with_password(user, password).await?;
     Ok(user)
 }
 
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Checks the paging bounds and turns the query into a filter; date ranges
+/// include both ends.
+fn user_filter(query: &UserListQuery) -> Result<(i64, i64, UserFilter), AppError> {
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_PAGE_SIZE);
+    if page < 1 {
+        return Err(AppError::BadRequest("page must be at least 1".to_string()));
+    }
+    if !(1..=MAX_PAGE_SIZE).contains(&per_page) {
+        return Err(AppError::BadRequest(format!("per_page must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+    // The offset of the page has to fit the query.
+    if (page - 1).checked_mul(per_page).is_none() {
+        return Err(AppError::BadRequest("page is too large".to_string()));
+    }
+    if let (Some(from), Some(to)) = (query.registered_from, query.registered_to) {
+        if from > to {
+            return Err(AppError::BadRequest("registered_from must not be after registered_to".to_string()));
+        }
+    }
+    let filter = UserFilter {
+        search: query.search.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string),
+        status: query.status,
+        is_active: query.is_active,
+        is_verified: query.is_verified,
+        two_factor_method: query.two_factor_method,
+        registered_from: query.registered_from.and_then(|d| d.and_hms_opt(0, 0, 0)),
+        registered_before: query
+            .registered_to
+            .and_then(|d| d.succ_opt())
+            .and_then(|d| d.and_hms_opt(0, 0, 0)),
+    };
+    Ok((page, per_page, filter))
+}
+
 pub(crate) async fn list_users(
     pool: Data<DbPool>,
-    limit: Option<i16>,
-    offsets: Option<i16>,
-    search: Option<String>,
-) -> Result<Vec<User>, Error> {
-    let users = UserRepository::new(pool).find_all(limit, offsets, search).await?;
-    Ok(users)
+    query: UserListQuery,
+) -> Result<Paginated<User>, Error> {
+    let (page, per_page, filter) = user_filter(&query)?;
+    let (items, total) = UserRepository::new(pool)
+        .find_page(
+            filter,
+            query.sort_by.unwrap_or_default(),
+            query.order.unwrap_or_default(),
+            per_page,
+            (page - 1) * per_page,
+        )
+        .await?;
+    Ok(Paginated { items, meta: PageMeta::new(page, per_page, total) })
+}
+
+const DEFAULT_SEARCH_LIMIT: i64 = 10;
+const MAX_SEARCH_LIMIT: i64 = 50;
+const MIN_SEARCH_LENGTH: usize = 2;
+
+/// Fuzzy lookup across username, email and phone number for admin screens.
+pub(crate) async fn search_users(
+    pool: Data<DbPool>,
+    query: UserSearchQuery,
+) -> Result<Vec<UserSearchHit>, Error> {
+    let term = query.q.trim();
+    if term.chars().count() < MIN_SEARCH_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "Search term must be at least {} characters",
+            MIN_SEARCH_LENGTH
+        ))
+        .into());
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+        return Err(AppError::BadRequest(format!("limit must be between 1 and {}", MAX_SEARCH_LIMIT)).into());
+    }
+    let hits = UserRepository::new(pool).search(term, limit).await?;
+    Ok(hits.into_iter().map(|(user, score)| UserSearchHit { user: user.into(), score }).collect())
+}
+
+fn cursor_at(user: &User, order: SortOrder, direction: KeysetDirection, filter: u64) -> String {
+    cursor::sign(&AccountCursor { created_at: user.created_at, id: user.id, order, direction, filter })
+}
+
+/// Keyset pagination over `(created_at, id)`: stable while rows are added or
+/// removed, and as cheap on the last page as on the first. The sort order is
+/// taken from the cursor once paging has started, and a cursor only continues
+/// the listing with the filter and order it was issued for.
+pub(crate) async fn list_users_by_cursor(
+    pool: Data<DbPool>,
+    query: UserListQuery,
+) -> Result<CursorPage<User>, Error> {
+    if query.page.is_some() {
+        return Err(AppError::BadRequest("page cannot be combined with cursor pagination".to_string()).into());
+    }
+    if query.sort_by.is_some_and(|field| field != UserSortField::CreatedAt) {
+        return Err(AppError::BadRequest("Cursor pagination is sorted by created_at".to_string()).into());
+    }
+    let position = query.cursor.as_deref().map(cursor::verify::<AccountCursor>).transpose()?;
+    let order = position.map_or(query.order.unwrap_or_default(), |p| p.order);
+    let (_, per_page, filter) = user_filter(&query)?;
+    let fingerprint = cursor::fingerprint(&filter);
+    if position.is_some_and(|p| p.filter != fingerprint || query.order.is_some_and(|o| o != p.order)) {
+        return Err(AppError::BadRequest("The cursor belongs to a listing with other filters or order".to_string()).into());
+    }
+
+    let mut items = UserRepository::new(pool)
+        .find_by_keyset(filter, order, position, per_page + 1)
+        .await?;
+    let more = items.len() as i64 > per_page;
+    items.truncate(per_page as usize);
+    let backwards = position.is_some_and(|p| p.direction == KeysetDirection::Before);
+    if backwards {
+        items.reverse();
+    }
+    // Walking forward there is something behind us iff we started from a
+    // cursor; walking back there is always something ahead.
+    let (has_next, has_prev) = if backwards { (true, more) } else { (more, position.is_some()) };
+    let next_cursor = items
+        .last()
+        .filter(|_| has_next)
+        .map(|user| cursor_at(user, order, KeysetDirection::After, fingerprint));
+    let prev_cursor = items
+        .first()
+        .filter(|_| has_prev)
+        .map(|user| cursor_at(user, order, KeysetDirection::Before, fingerprint));
+    Ok(CursorPage { items, per_page, next_cursor, prev_cursor })
 }
+pub(crate) async fn find_account(pool: Data<DbPool>, id: Uuid) -> Result<User, Error> {
+    UserRepository::new(pool).find_by_id(id).await
+}
+
+/// Fetches an account the caller may read: their own, or any with `users` read.
 pub(crate) async fn find_user_by_id(
     pool: Data<DbPool>,
+    engine: &PolicyEngine,
+    caller: Uuid,
     id: String,
 ) -> Result<User, Error> {
-    let id =Uuid::parse_str(&*id.to_string()).unwrap();
+    let id = authorize_account(pool.clone(), engine, caller, "read", id).await?;
     let user = UserRepository::new(pool).find_by_id(id).await?;
     Ok(user)
 }
 
-pub(crate) async fn update_user(
+pub(crate) async fn find_user_by_username(pool: Data<DbPool>, username: String) -> Result<User, Error> {
+    let (user, _) = UserRepository::new(pool).find_by_username(&username).await?;
+    Ok(user)
+}
+
+pub(crate) async fn find_user_by_email(pool: Data<DbPool>, email: String) -> Result<User, Error> {
+    UserRepository::new(pool).find_by_email(&email).await
+}
+
+/// Parses the account id and checks the policies let `caller` perform
+/// `action` on it: owners act on their own account, anyone else needs the
+/// matching `users` permission.
+pub(crate) async fn authorize_account(
     pool: Data<DbPool>,
+    engine: &PolicyEngine,
+    caller: Uuid,
+    action: &str,
     id: String,
-    user: NewUser,
+) -> Result<Uuid, Error> {
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::BadRequest("Invalid user id".to_string()))?;
+    policy_services::authorize(pool, engine, caller, action, PolicyResource::account(id)).await?;
+    Ok(id)
+}
+
+/// Applies `changes` on behalf of `caller`, enforcing the rules every way of
+/// editing an account shares: only account managers change activation and
+/// verification, status only moves through [`change_status`], phone-based 2FA
+/// needs a verified, unchanged number, WebAuthn needs a registered key, and a
+/// new number has to be verified again. `expected_versions` comes from the
+/// caller's `If-Match`; edits based on a stale copy fail with `412`.
+pub(crate) async fn apply_changes(
+    pool: Data<DbPool>,
+    caller: Uuid,
+    id: Uuid,
+    expected_versions: Option<Vec<i32>>,
+    mut changes: UserChangeset,
 ) -> Result<User, Error> {
-    let id =Uuid::parse_str(&*id.to_string()).unwrap();
+    if let Some(Some(raw)) = changes.phone_number.as_ref() {
+        changes.phone_number = match raw.trim() {
+            "" => Some(None),
+            raw => Some(Some(normalize_phone_number(raw)?)),
+        };
+    }
 
-    let user = UserRepository::new(pool).update(id, user).await?;
-    Ok(user)
+    let repo = UserRepository::new(pool.clone());
+    let current = repo.find_by_id(id).await?;
+    if changes.changes_managed_fields()
+        && !PermissionService::new(pool.clone())
+            .has_permission(caller, "users", PermissionAction::Update)
+            .await?
+    {
+        return Err(AppError::Forbidden(
+            "Only account administrators can change status or verification".to_string(),
+        )
+        .into());
+    }
+    if changes.status.is_some_and(|status| status != current.status) {
+        return Err(AppError::ValidationError(
+            "Account status changes go through the status endpoints and need a reason".to_string(),
+        )
+        .into());
+    }
+    let phone_changed = changes
+        .phone_number
+        .as_ref()
+        .is_some_and(|phone| *phone != current.phone_number);
+    let method = changes.two_factor_method.unwrap_or(current.two_factor_method);
+    if method.uses_phone() && (phone_changed || !current.phone_verified) {
+        return Err(phone_method_requires_verified_phone().into());
+    }
+    if method == TwoFactorMethodEnum::Webauthn && !webauthn_services::has_credentials(pool, id).await? {
+        return Err(webauthn_requires_credential().into());
+    }
+    if phone_changed {
+        changes.phone_verified = Some(false);
+    }
+    repo.update_fields(id, expected_versions, changes).await
 }
 
 pub(crate) async fn delete_user(
     pool: Data<DbPool>,
-    id: String,
+    caller: Uuid,
+    id: Uuid,
+    expected_versions: Option<Vec<i32>>,
 ) -> Result<(), Error> {
-    let id =Uuid::parse_str(&*id.to_string()).unwrap();
-
-    UserRepository::new(pool).delete(id).await?;
+    UserRepository::new(pool).soft_delete(id, Some(caller), expected_versions).await?;
+    authentication::forget_session(id);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Undoes a soft delete while the account is still inside its restore period.
+pub(crate) async fn restore_user(pool: Data<DbPool>, caller: Uuid, id: String) -> Result<User, Error> {
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::BadRequest("Invalid user id".to_string()))?;
+    let grace = chrono::Duration::days(RETENTION_SETTINGS.restore_grace_days);
+    let user = UserRepository::new(pool)
+        .restore(id, chrono::Utc::now().naive_utc() - grace, caller)
+        .await?;
+    authentication::forget_session(id);
+    Ok(user)
+}
+
+/// Suspends, locks, unlocks or reactivates an account. Transitions the
+/// lifecycle does not allow are rejected; leaving `Active` revokes every
+/// token issued to the account so far.
+pub(crate) async fn change_status(
+    pool: Data<DbPool>,
+    caller: Uuid,
+    id: String,
+    action: StatusAction,
+    reason: &str,
+) -> Result<User, Error> {
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::BadRequest("Invalid user id".to_string()))?;
+    let reason = status_reason(reason)?;
+    let repo = UserRepository::new(pool);
+    let current = repo.find_by_id(id).await?;
+    let to_status = current.status.apply(action).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "Cannot {} an account that is {}",
+            action.as_str(),
+            current.status.as_str()
+        ))
+    })?;
+    let user = repo
+        .change_status(NewStatusChange {
+            account_id: id,
+            from_status: current.status,
+            to_status,
+            reason,
+            changed_by: Some(caller),
+        })
+        .await?;
+    authentication::forget_session(id);
+    Ok(user)
+}
+
+const MAX_REASON_LENGTH: usize = 500;
+
+fn status_reason(reason: &str) -> Result<String, AppError> {
+    match reason.trim() {
+        "" => Err(AppError::ValidationError("A reason is required to change an account's status".to_string())),
+        reason if reason.chars().count() > MAX_REASON_LENGTH => Err(AppError::ValidationError(format!(
+            "The reason must be at most {} characters",
+            MAX_REASON_LENGTH
+        ))),
+        reason => Ok(reason.to_string()),
+    }
+}
+
+pub(crate) async fn status_history(pool: Data<DbPool>, id: String) -> Result<Vec<StatusChange>, Error> {
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::BadRequest("Invalid user id".to_string()))?;
+    UserRepository::new(pool).status_history(id).await
+}
+
+/// Hard deletes every account whose restore period has run out.
+pub(crate) async fn purge_deleted_accounts(pool: Data<DbPool>) -> Result<usize, Error> {
+    let grace = chrono::Duration::days(RETENTION_SETTINGS.restore_grace_days);
+    UserRepository::new(pool)
+        .purge_deleted(chrono::Utc::now().naive_utc() - grace)
+        .await
+}
+
+/// Runs [`purge_deleted_accounts`] on the configured interval for as long as
+/// the server is up.
+pub(crate) fn spawn_account_purge(pool: Data<DbPool>) {
+    let period = std::time::Duration::from_secs(RETENTION_SETTINGS.purge_interval_minutes.max(1) * 60);
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(period);
+        loop {
+            interval.tick().await;
+            match purge_deleted_accounts(pool.clone()).await {
+                Ok(0) => {}
+                Ok(purged) => log::info!("purged {} deleted accounts", purged),
+                Err(e) => log::error!("account purge failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Sends a code to the caller's current phone number over SMS or WhatsApp.
+pub(crate) async fn request_phone_verification(
+    pool: Data<DbPool>,
+    senders: &OtpSenders,
+    id: Uuid,
+    method: TwoFactorMethodEnum,
+) -> Result<OtpChallenge, Error> {
+    if !method.uses_phone() {
+        return Err(AppError::BadRequest("Phone numbers are verified over Sms or Whatsapp".to_string()).into());
+    }
+    let user = UserRepository::new(pool.clone()).find_by_id(id).await?;
+    if user.phone_verified {
+        return Err(AppError::BadRequest("Phone number is already verified".to_string()).into());
+    }
+    otp_services::issue_otp(pool, senders, &user, method, OtpPurpose::VerifyPhone).await
+}
+
+/// Marks the phone number verified if the code was sent to the number the
+/// account still has.
+pub(crate) async fn confirm_phone_verification(
+    pool: Data<DbPool>,
+    id: Uuid,
+    challenge_id: Uuid,
+    code: &str,
+) -> Result<User, Error> {
+    let otp = otp_services::verify_otp(pool.clone(), challenge_id, code, OtpPurpose::VerifyPhone).await?;
+    let repo = UserRepository::new(pool);
+    let user = repo.find_by_id(id).await?;
+    if otp.user_id != user.id || user.phone_number.as_deref() != Some(otp.recipient.as_str()) {
+        return Err(AppError::Unauthorized("Invalid or expired verification code".to_string()).into());
+    }
+    repo.set_phone_verified(id, true).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn page_size_is_bounded() {
+        for per_page in [0, MAX_PAGE_SIZE + 1] {
+            let query = UserListQuery { per_page: Some(per_page), ..Default::default() };
+            assert!(user_filter(&query).is_err());
+        }
+        let query = UserListQuery { page: Some(0), ..Default::default() };
+        assert!(user_filter(&query).is_err());
+        let query = UserListQuery { page: Some(i64::MAX), per_page: Some(MAX_PAGE_SIZE), ..Default::default() };
+        assert!(user_filter(&query).is_err());
+        let (page, per_page, _) = user_filter(&UserListQuery::default()).unwrap();
+        assert_eq!((page, per_page), (1, DEFAULT_PAGE_SIZE));
+    }
+
+    #[test]
+    fn registration_range_includes_both_days() {
+        let query = UserListQuery {
+            registered_from: NaiveDate::from_ymd_opt(2024, 1, 1),
+            registered_to: NaiveDate::from_ymd_opt(2024, 1, 31),
+            ..Default::default()
+        };
+        let (_, _, filter) = user_filter(&query).unwrap();
+        assert_eq!(filter.registered_from, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0));
+        assert_eq!(filter.registered_before, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap().and_hms_opt(0, 0, 0));
+
+        let reversed = UserListQuery {
+            registered_from: query.registered_to,
+            registered_to: query.registered_from,
+            ..Default::default()
+        };
+        assert!(user_filter(&reversed).is_err());
+    }
+
+    #[test]
+    fn page_meta_counts_partial_pages() {
+        assert_eq!(PageMeta::new(1, 20, 0).total_pages, 0);
+        let meta = PageMeta::new(2, 20, 41);
+        assert_eq!(meta.total_pages, 3);
+        assert!(meta.has_next && meta.has_prev);
+        assert!(!PageMeta::new(3, 20, 41).has_next);
+    }
+
+    #[test]
+    fn status_lifecycle_rejects_invalid_transitions() {
+        use AccountStatusEnum::*;
+        assert_eq!(Active.apply(StatusAction::Suspend), Some(Suspended));
+        assert_eq!(Active.apply(StatusAction::Lock), Some(Locked));
+        assert_eq!(Locked.apply(StatusAction::Suspend), Some(Suspended));
+        assert_eq!(Locked.apply(StatusAction::Unlock), Some(Active));
+        assert_eq!(Suspended.apply(StatusAction::Reactivate), Some(Active));
+
+        assert_eq!(Active.apply(StatusAction::Unlock), None);
+        assert_eq!(Suspended.apply(StatusAction::Lock), None);
+        assert_eq!(Suspended.apply(StatusAction::Unlock), None);
+        assert_eq!(Locked.apply(StatusAction::Reactivate), None);
+        for action in [StatusAction::Suspend, StatusAction::Lock, StatusAction::Unlock, StatusAction::Reactivate] {
+            assert_eq!(Deleted.apply(action), None);
+        }
+    }
+
+    #[test]
+    fn status_changes_need_a_reason() {
+        assert!(status_reason("  ").is_err());
+        assert!(status_reason(&"x".repeat(MAX_REASON_LENGTH + 1)).is_err());
+        assert_eq!(status_reason(" Chargeback fraud ").unwrap(), "Chargeback fraud");
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use actix_web::{web, Error};
+use chrono::{Duration, NaiveDateTime, Utc};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use uuid::Uuid;
+use crate::api::dto::requests::webauthn::{AssertionCredential, RegistrationFinishRequest};
+use crate::config::database::DbPool;
+use crate::config::webauthn::WEBAUTHN_SETTINGS;
+use crate::domain::models::user::{TwoFactorMethodEnum, User};
+use crate::domain::models::webauthn::{
+    AuthenticatorSelection, Ceremony, CreationOptions, CredentialDescriptor, CredentialParameter,
+    NewWebauthnChallenge, NewWebauthnCredential, RelyingParty, RequestOptions, UserEntity,
+    WebauthnCredential, CEREMONY_AUTHENTICATION, CEREMONY_REGISTRATION,
+};
+use crate::domain::repositories::user_repository::UserRepository;
+use crate::domain::repositories::webauthn_repository::WebauthnRepository;
+use crate::domain::repository::Repository;
+use crate::domain::services::authentication::{ensure_not_locked, issue_tokens, AMR_OTP, AMR_PASSWORD, AMR_WEBAUTHN};
+use crate::utils::errors::AppError;
+use crate::utils::webauthn::{
+    check_sign_count, decode, encode, parse_attestation_object, verify_assertion_signature,
+    verify_client_data, AuthenticatorData, COSE_ALG_ES256,
+};
+
+fn new_challenge() -> Vec<u8> {
+    let mut challenge = vec![0u8; 32];
+    OsRng.fill_bytes(&mut challenge);
+    challenge
+}
+
+fn descriptor(credential: &WebauthnCredential) -> CredentialDescriptor {
+    CredentialDescriptor {
+        kind: "public-key",
+        id: encode(&credential.credential_id),
+        transports: serde_json::from_value(credential.transports.clone()).unwrap_or_default(),
+    }
+}
+
+fn timeout_ms() -> u64 {
+    (WEBAUTHN_SETTINGS.timeout_seconds * 1000) as u64
+}
+
+async fn store_challenge(
+    repo: &WebauthnRepository,
+    user_id: Option<Uuid>,
+    ceremony: &str,
+    after_password: bool,
+) -> Result<(Uuid, Vec<u8>), Error> {
+    let challenge = new_challenge();
+    let stored = repo
+        .create_challenge(NewWebauthnChallenge {
+            user_id,
+            challenge: challenge.clone(),
+            ceremony: ceremony.to_string(),
+            after_password,
+            expires_at: Utc::now().naive_utc() + Duration::seconds(WEBAUTHN_SETTINGS.timeout_seconds),
+        })
+        .await?;
+    Ok((stored.id, challenge))
+}
+
+/// Adding a passkey is as sensitive as changing the password: the sign in
+/// must be recent, and an account that already has a second factor must have
+/// signed in with one.
+fn ensure_step_up(
+    user: &User,
+    has_credentials: bool,
+    signed_in_at: NaiveDateTime,
+    methods: &[String],
+) -> Result<(), AppError> {
+    let max_age = Duration::seconds(WEBAUTHN_SETTINGS.reauthentication_seconds);
+    if signed_in_at + max_age < Utc::now().naive_utc() {
+        return Err(AppError::Unauthorized("Sign in again to register a security key".to_string()));
+    }
+    let has_factor = has_credentials || user.two_factor_method != TwoFactorMethodEnum::None;
+    let used_factor = methods.iter().any(|method| method == AMR_OTP || method == AMR_WEBAUTHN);
+    if has_factor && !used_factor {
+        return Err(AppError::Forbidden(
+            "Sign in with your second factor to register a security key".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Begins registering a new passkey for the signed in account.
+pub(crate) async fn start_registration(
+    pool: web::Data<DbPool>,
+    id: Uuid,
+    signed_in_at: NaiveDateTime,
+    methods: &[String],
+) -> Result<Ceremony<CreationOptions>, Error> {
+    let user = UserRepository::new(pool.clone()).find_by_id(id).await?;
+    let repo = WebauthnRepository::new(pool);
+    let existing = repo.find_credentials_by_user(user.id).await?;
+    ensure_step_up(&user, !existing.is_empty(), signed_in_at, methods)?;
+    let (ceremony_id, challenge) = store_challenge(&repo, Some(user.id), CEREMONY_REGISTRATION, false).await?;
+
+    Ok(Ceremony {
+        ceremony_id,
+        public_key: CreationOptions {
+            rp: RelyingParty {
+                id: WEBAUTHN_SETTINGS.rp_id.clone(),
+                name: WEBAUTHN_SETTINGS.rp_name.clone(),
+            },
+            user: UserEntity {
+                id: encode(user.id.as_bytes()),
+                name: user.username.clone(),
+                display_name: user.username,
+            },
+            challenge: encode(&challenge),
+            pub_key_cred_params: vec![CredentialParameter {
+                kind: "public-key",
+                alg: COSE_ALG_ES256,
+            }],
+            timeout: timeout_ms(),
+            attestation: "none",
+            exclude_credentials: existing.iter().map(descriptor).collect(),
+            authenticator_selection: AuthenticatorSelection {
+                resident_key: "preferred",
+                user_verification: "preferred",
+            },
+        },
+    })
+}
+
+/// Verifies the authenticator's attestation and stores the new credential.
+pub(crate) async fn finish_registration(
+    pool: web::Data<DbPool>,
+    id: Uuid,
+    signed_in_at: NaiveDateTime,
+    methods: &[String],
+    payload: RegistrationFinishRequest,
+) -> Result<WebauthnCredential, Error> {
+    let user = UserRepository::new(pool.clone()).find_by_id(id).await?;
+    let repo = WebauthnRepository::new(pool);
+    let existing = repo.find_credentials_by_user(user.id).await?;
+    ensure_step_up(&user, !existing.is_empty(), signed_in_at, methods)?;
+    let challenge = repo.take_challenge(payload.ceremony_id, CEREMONY_REGISTRATION).await?;
+    if challenge.user_id != Some(id) || challenge.expires_at < Utc::now().naive_utc() {
+        return Err(AppError::Unauthorized("Registration ceremony expired or invalid".to_string()).into());
+    }
+
+    let response = payload.credential.response;
+    let client_data = decode(&response.client_data_json)?;
+    verify_client_data(&client_data, "webauthn.create", &challenge.challenge, &WEBAUTHN_SETTINGS.origin)?;
+
+    let auth_data = parse_attestation_object(&decode(&response.attestation_object)?)?;
+    auth_data.verify_for(&WEBAUTHN_SETTINGS.rp_id)?;
+    let credential = auth_data
+        .attested_credential
+        .ok_or_else(|| AppError::BadRequest("Attestation carries no credential".to_string()))?;
+
+    let credential = repo
+        .create_credential(NewWebauthnCredential {
+            user_id: id,
+            credential_id: credential.credential_id,
+            public_key: credential.public_key,
+            sign_count: auth_data.sign_count as i64,
+            transports: serde_json::json!(response.transports),
+            name: payload.name,
+        })
+        .await?;
+    Ok(credential)
+}
+
+/// Builds assertion options for a known account; used both for passwordless
+/// sign in with a username and, with `after_password`, as the second step of
+/// a password login. Only the latter may skip user verification.
+pub(crate) async fn start_authentication_for(
+    pool: web::Data<DbPool>,
+    user: &User,
+    after_password: bool,
+) -> Result<Ceremony<RequestOptions>, Error> {
+    let repo = WebauthnRepository::new(pool);
+    let credentials = repo.find_credentials_by_user(user.id).await?;
+    if credentials.is_empty() {
+        return Err(AppError::BadRequest("No security keys registered for this account".to_string()).into());
+    }
+    let (ceremony_id, challenge) = store_challenge(&repo, Some(user.id), CEREMONY_AUTHENTICATION, after_password).await?;
+    Ok(Ceremony {
+        ceremony_id,
+        public_key: RequestOptions {
+            challenge: encode(&challenge),
+            rp_id: WEBAUTHN_SETTINGS.rp_id.clone(),
+            timeout: timeout_ms(),
+            allow_credentials: credentials.iter().map(descriptor).collect(),
+            user_verification: if after_password { "preferred" } else { "required" },
+        },
+    })
+}
+
+pub(crate) async fn start_authentication(
+    pool: web::Data<DbPool>,
+    username: Option<String>,
+) -> Result<Ceremony<RequestOptions>, Error> {
+    if let Some(username) = username {
+        let (user, _) = UserRepository::new(pool.clone())
+            .find_by_login(&username)
+            .await
+            .map_err(|_| AppError::BadRequest("No security keys registered for this account".to_string()))?;
+        return start_authentication_for(pool, &user, false).await;
+    }
+
+    // Discoverable credentials: the authenticator picks the account.
+    let repo = WebauthnRepository::new(pool);
+    let (ceremony_id, challenge) = store_challenge(&repo, None, CEREMONY_AUTHENTICATION, false).await?;
+    Ok(Ceremony {
+        ceremony_id,
+        public_key: RequestOptions {
+            challenge: encode(&challenge),
+            rp_id: WEBAUTHN_SETTINGS.rp_id.clone(),
+            timeout: timeout_ms(),
+            allow_credentials: vec![],
+            user_verification: "required",
+        },
+    })
+}
+
+/// The sign in methods an assertion completes. Without a password first the
+/// key alone must prove who holds it, so the authenticator has to have
+/// verified its user (PIN or biometric).
+fn assertion_methods(after_password: bool, user_verified: bool) -> Result<&'static [&'static str], AppError> {
+    match (after_password, user_verified) {
+        (true, _) => Ok(&[AMR_PASSWORD, AMR_WEBAUTHN]),
+        (false, true) => Ok(&[AMR_WEBAUTHN]),
+        (false, false) => Err(AppError::Unauthorized("User verification is required".to_string())),
+    }
+}
+
+/// Verifies an assertion and issues the regular access and refresh tokens.
+pub(crate) async fn finish_authentication(
+    pool: web::Data<DbPool>,
+    ceremony_id: Uuid,
+    credential: AssertionCredential,
+) -> Result<HashMap<String, String>, Error> {
+    let repo = WebauthnRepository::new(pool.clone());
+    let challenge = repo.take_challenge(ceremony_id, CEREMONY_AUTHENTICATION).await?;
+    if challenge.expires_at < Utc::now().naive_utc() {
+        return Err(AppError::Unauthorized("Authentication ceremony expired".to_string()).into());
+    }
+
+    let credential_id = decode(credential.raw_id.as_deref().unwrap_or(&credential.id))?;
+    let stored = repo.find_credential(credential_id).await?;
+    if challenge.user_id.is_some_and(|user_id| user_id != stored.user_id) {
+        return Err(AppError::Unauthorized("Credential does not belong to this account".to_string()).into());
+    }
+
+    let response = credential.response;
+    let client_data = decode(&response.client_data_json)?;
+    verify_client_data(&client_data, "webauthn.get", &challenge.challenge, &WEBAUTHN_SETTINGS.origin)?;
+
+    let raw_auth_data = decode(&response.authenticator_data)?;
+    let auth_data = AuthenticatorData::parse(&raw_auth_data)?;
+    auth_data.verify_for(&WEBAUTHN_SETTINGS.rp_id)?;
+    let methods = assertion_methods(challenge.after_password, auth_data.user_verified())?;
+    verify_assertion_signature(&stored.public_key, &raw_auth_data, &client_data, &decode(&response.signature)?)?;
+    check_sign_count(stored.sign_count as u32, auth_data.sign_count)?;
+    repo.record_use(stored.id, auth_data.sign_count as i64).await?;
+
+    let repo = UserRepository::new(pool.clone());
+    let user = repo.find_by_id(stored.user_id).await?;
+    ensure_not_locked(&user)?;
+    let user = repo.record_successful_login(user.id).await?;
+    issue_tokens(pool, &user, methods).await
+}
+
+pub(crate) async fn has_credentials(pool: web::Data<DbPool>, user_id: Uuid) -> Result<bool, Error> {
+    Ok(!WebauthnRepository::new(pool).find_credentials_by_user(user_id).await?.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::user::AccountStatusEnum;
+
+    fn user(two_factor_method: TwoFactorMethodEnum) -> User {
+        let now = Utc::now().naive_utc();
+        User {
+            id: Uuid::new_v4(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            is_active: true,
+            is_verified: true,
+            phone_number: None,
+            status: AccountStatusEnum::Active,
+            last_login: None,
+            two_factor_method,
+            registration_date: now,
+            preferred_language: "en".to_string(),
+            login_attempts: 0,
+            created_at: now,
+            updated_at: now,
+            phone_verified: false,
+            locked_until: None,
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn registering_a_key_needs_a_recent_sign_in_with_the_existing_factor() {
+        let now = Utc::now().naive_utc();
+        let password = vec![AMR_PASSWORD.to_string()];
+        let with_otp = vec![AMR_PASSWORD.to_string(), AMR_OTP.to_string()];
+        let stale = now - Duration::seconds(WEBAUTHN_SETTINGS.reauthentication_seconds + 1);
+
+        assert!(ensure_step_up(&user(TwoFactorMethodEnum::None), false, now, &password).is_ok());
+        assert!(matches!(
+            ensure_step_up(&user(TwoFactorMethodEnum::None), false, stale, &password),
+            Err(AppError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            ensure_step_up(&user(TwoFactorMethodEnum::Sms), false, now, &password),
+            Err(AppError::Forbidden(_))
+        ));
+        assert!(matches!(
+            ensure_step_up(&user(TwoFactorMethodEnum::None), true, now, &password),
+            Err(AppError::Forbidden(_))
+        ));
+        assert!(ensure_step_up(&user(TwoFactorMethodEnum::Sms), false, now, &with_otp).is_ok());
+    }
+
+    #[test]
+    fn passwordless_assertions_need_user_verification() {
+        assert_eq!(assertion_methods(false, true).unwrap(), [AMR_WEBAUTHN]);
+        assert!(matches!(assertion_methods(false, false), Err(AppError::Unauthorized(_))));
+        assert_eq!(assertion_methods(true, false).unwrap(), [AMR_PASSWORD, AMR_WEBAUTHN]);
+        assert_eq!(assertion_methods(true, true).unwrap(), [AMR_PASSWORD, AMR_WEBAUTHN]);
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::error::Error;
+
+use actix_web::web;
+use diesel::migration::MigrationSource;
+use diesel::pg::Pg;
+use diesel::{Connection, PgConnection};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+use crate::config::database::POOL_SETTINGS;
+use crate::utils::errors::AppError;
+
+/// Every migration under `migrations/`, compiled into the binary so a
+/// deployment needs nothing but the executable and a database.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+fn migration_error(e: Box<dyn Error + Send + Sync>) -> AppError {
+    log::error!("Migration failed: {}", e);
+    AppError::InternalError(format!("Migration failed: {}", e))
+}
+
+/// Applies every pending migration, oldest first, and returns the versions
+/// it applied. Each migration runs in its own transaction.
+pub fn run_pending(conn: &mut PgConnection) -> Result<Vec<String>, AppError> {
+    let applied = conn.run_pending_migrations(MIGRATIONS).map_err(migration_error)?;
+    Ok(applied.iter().map(|version| version.to_string()).collect())
+}
+
+/// Reverts the most recently applied migration; `None` when there is none.
+pub fn revert_last(conn: &mut PgConnection) -> Result<Option<String>, AppError> {
+    if conn.applied_migrations().map_err(migration_error)?.is_empty() {
+        return Ok(None);
+    }
+    let version = conn.revert_last_migration(MIGRATIONS).map_err(migration_error)?;
+    Ok(Some(version.to_string()))
+}
+
+/// Every embedded migration, oldest first, with whether it has been applied.
+pub fn status(conn: &mut PgConnection) -> Result<Vec<(String, bool)>, AppError> {
+    let applied: HashSet<String> = conn
+        .applied_migrations()
+        .map_err(migration_error)?
+        .iter()
+        .map(|version| version.to_string())
+        .collect();
+    let mut migrations: Vec<(String, bool)> = MigrationSource::<Pg>::migrations(&MIGRATIONS)
+        .map_err(migration_error)?
+        .iter()
+        .map(|migration| {
+            let name = migration.name();
+            (name.to_string(), applied.contains(&name.version().to_string()))
+        })
+        .collect();
+    migrations.sort();
+    Ok(migrations)
+}
+
+/// Migrations run on a plain blocking connection of their own rather than
+/// one from the async pool, with the same TLS settings.
+fn connect(database_url: &str) -> Result<PgConnection, AppError> {
+    PgConnection::establish(&POOL_SETTINGS.libpq_url(database_url))
+        .map_err(|e| AppError::ServiceUnavailable(format!("Failed to connect: {}", e)))
+}
+
+/// Applies pending migrations before the server starts accepting requests,
+/// when `RUN_MIGRATIONS` asks for it.
+pub async fn migrate_on_startup(database_url: String) -> Result<(), AppError> {
+    let applied = web::block(move || run_pending(&mut connect(&database_url)?))
+        .await
+        .map_err(|e| AppError::InternalError(format!("Migration failed: {}", e)))??;
+    for version in applied {
+        log::info!("Applied migration {}", version);
+    }
+    Ok(())
+}
+
+/// `migrate up|down|status`: applies every pending migration, reverts the
+/// latest one, or lists them all against `DATABASE_URL`.
+pub fn command(args: &[String]) -> Result<(), AppError> {
+    let action = args.first().map(String::as_str).unwrap_or("status");
+    if !matches!(action, "up" | "down" | "status") {
+        return Err(AppError::BadRequest(format!(
+            "Unknown migrate command `{}`, expected up, down or status",
+            action
+        )));
+    }
+
+    dotenvy::dotenv().ok();
+    let mut conn = connect(&crate::config::get_database_url())?;
+    match action {
+        "up" => {
+            let applied = run_pending(&mut conn)?;
+            if applied.is_empty() {
+                println!("Database is up to date");
+            }
+            for version in applied {
+                println!("Applied {}", version);
+            }
+        }
+        "down" => match revert_last(&mut conn)? {
+            Some(version) => println!("Reverted {}", version),
+            None => println!("No migration to revert"),
+        },
+        _ => {
+            for (name, applied) in status(&mut conn)? {
+                println!("[{}] {}", if applied { "x" } else { " " }, name);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::sql_types::{Nullable, Text};
+    use diesel::{sql_query, QueryableByName, RunQueryDsl};
+    use crate::infrastructure::database::test_database::TestDatabase;
+
+    #[derive(QueryableByName, Debug, PartialEq, Eq, Hash)]
+    struct ColumnInfo {
+        #[diesel(sql_type = Text)]
+        table_name: String,
+        #[diesel(sql_type = Text)]
+        column_name: String,
+        #[diesel(sql_type = Text)]
+        udt_name: String,
+        #[diesel(sql_type = Text)]
+        is_nullable: String,
+        #[diesel(sql_type = Nullable<Text>)]
+        max_length: Option<String>,
+    }
+
+    /// The Postgres type name information_schema reports for a diesel type.
+    fn udt_name(diesel_type: &str) -> String {
+        match diesel_type {
+            "Bool" => "bool".to_string(),
+            "Uuid" | "Text" | "Varchar" | "Int4" | "Int8" | "Timestamptz" | "Bytea" | "Json" | "Inet" => {
+                diesel_type.to_lowercase()
+            }
+            // Custom types are named after the Postgres enum, in CamelCase.
+            custom => custom
+                .chars()
+                .enumerate()
+                .flat_map(|(i, c)| {
+                    let separator = (i > 0 && c.is_uppercase()).then_some('_');
+                    separator.into_iter().chain(c.to_lowercase())
+                })
+                .collect(),
+        }
+    }
+
+    /// Reads every column declared by the `table!` blocks of `schemas.rs`.
+    fn declared_columns() -> HashSet<ColumnInfo> {
+        let mut columns = HashSet::new();
+        let mut table = None;
+        let mut max_length = None;
+        for line in include_str!("schemas/schemas.rs").lines().map(str::trim) {
+            if line == "diesel::table! {" || line == "}" {
+                table = None;
+            } else if let Some(name) = line.strip_suffix('{').and_then(|l| l.split_once(" (")) {
+                table = Some(name.0.to_string());
+            } else if let Some(length) = line.strip_prefix("#[max_length = ") {
+                max_length = Some(length.trim_end_matches(']').to_string());
+            } else if let (Some(table), Some((name, ty))) = (&table, line.split_once(" -> ")) {
+                let ty = ty.trim_end_matches(',');
+                let (ty, nullable) = match ty.strip_prefix("Nullable<") {
+                    Some(inner) => (inner.trim_end_matches('>'), "YES"),
+                    None => (ty, "NO"),
+                };
+                columns.insert(ColumnInfo {
+                    table_name: table.clone(),
+                    column_name: name.to_string(),
+                    udt_name: udt_name(ty),
+                    is_nullable: nullable.to_string(),
+                    max_length: max_length.take(),
+                });
+            }
+        }
+        columns
+    }
+
+    fn migrated_columns(conn: &mut PgConnection) -> HashSet<ColumnInfo> {
+        sql_query(
+            "SELECT table_name::text, column_name::text, udt_name::text, is_nullable::text, \
+                    character_maximum_length::text AS max_length \
+             FROM information_schema.columns \
+             WHERE table_schema = 'public' AND table_name <> '__diesel_schema_migrations'",
+        )
+        .load(conn)
+        .expect("information_schema is readable")
+        .into_iter()
+        .collect()
+    }
+
+    /// Runs every migration against a throwaway database on the server of
+    /// `TEST_DATABASE_URL`, and skips when that is not set.
+    #[test]
+    fn migrations_produce_the_diesel_schema_and_revert_cleanly() {
+        let Some(database) = TestDatabase::create() else {
+            return;
+        };
+        let mut conn = database.connect();
+        run_pending(&mut conn).unwrap();
+        assert!(status(&mut conn).unwrap().iter().all(|(_, applied)| *applied));
+        let (migrated, declared) = (migrated_columns(&mut conn), declared_columns());
+        let drift: Vec<_> = migrated.symmetric_difference(&declared).collect();
+        assert!(drift.is_empty(), "migrations and schemas.rs disagree on {:#?}", drift);
+
+        while revert_last(&mut conn).unwrap().is_some() {}
+        assert!(migrated_columns(&mut conn).is_empty());
+    }
+}
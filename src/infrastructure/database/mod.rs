@@ -1 +1,5 @@
-pub mod schemas;
\ No newline at end of file
+pub mod migrations;
+pub mod schemas;
+pub mod unit_of_work;
+#[cfg(test)]
+pub(crate) mod test_database;
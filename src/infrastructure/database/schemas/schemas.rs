@@ -22,6 +22,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::AccountStatusEnum;
+
+    account_status_history (id) {
+        id -> Uuid,
+        account_id -> Uuid,
+        from_status -> AccountStatusEnum,
+        to_status -> AccountStatusEnum,
+        reason -> Text,
+        changed_by -> Nullable<Uuid>,
+        changed_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::AccountStatusEnum;
@@ -44,6 +59,10 @@ diesel::table! {
         login_attempts -> Int4,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        phone_verified -> Bool,
+        locked_until -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
+        version -> Int4,
     }
 }
 
@@ -65,6 +84,38 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    magic_links (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        nonce_hash -> Bytea,
+        expires_at -> Timestamptz,
+        consumed_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TwoFactorMethodEnum;
+
+    otp_codes (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        method -> TwoFactorMethodEnum,
+        #[max_length = 50]
+        purpose -> Varchar,
+        #[max_length = 255]
+        recipient -> Varchar,
+        code_hash -> Bytea,
+        attempts -> Int4,
+        max_attempts -> Int4,
+        expires_at -> Timestamptz,
+        consumed_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
 
@@ -105,6 +156,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    role_parents (role_id, parent_id) {
+        role_id -> Uuid,
+        parent_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     role_permissions (role_id, permission_id) {
         role_id -> Uuid,
@@ -143,23 +202,61 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    webauthn_challenges (id) {
+        id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        challenge -> Bytea,
+        #[max_length = 50]
+        ceremony -> Varchar,
+        after_password -> Bool,
+        expires_at -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webauthn_credentials (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        credential_id -> Bytea,
+        public_key -> Bytea,
+        sign_count -> Int8,
+        transports -> Json,
+        #[max_length = 255]
+        name -> Nullable<Varchar>,
+        last_used_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::joinable!(account_roles -> accounts (account_id));
 diesel::joinable!(account_roles -> roles (role_id));
 diesel::joinable!(auth_tokens -> accounts (sub));
+diesel::joinable!(magic_links -> accounts (user_id));
+diesel::joinable!(otp_codes -> accounts (user_id));
 diesel::joinable!(password_hashes -> accounts (user_id));
 diesel::joinable!(password_reset_tokens -> accounts (user_id));
 diesel::joinable!(role_permissions -> permissions (permission_id));
 diesel::joinable!(role_permissions -> roles (role_id));
 diesel::joinable!(two_factor_methods -> accounts (user_id));
+diesel::joinable!(webauthn_challenges -> accounts (user_id));
+diesel::joinable!(webauthn_credentials -> accounts (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     account_roles,
+    account_status_history,
     accounts,
     auth_tokens,
+    magic_links,
+    otp_codes,
     password_hashes,
     password_reset_tokens,
     permissions,
+    role_parents,
     role_permissions,
     roles,
     two_factor_methods,
+    webauthn_challenges,
+    webauthn_credentials,
 );
@@ -0,0 +1,54 @@
+use diesel::{sql_query, Connection, PgConnection, RunQueryDsl};
+use std::env;
+
+use crate::infrastructure::database::migrations::run_pending;
+
+/// A throwaway database on the server of `TEST_DATABASE_URL`, dropped again
+/// when the value goes out of scope, even if the test panicked.
+pub(crate) struct TestDatabase {
+    admin_url: String,
+    name: String,
+    pub(crate) url: String,
+}
+
+impl TestDatabase {
+    /// An empty database; `None`, after saying so, when `TEST_DATABASE_URL`
+    /// is not set and the test should be skipped.
+    pub(crate) fn create() -> Option<TestDatabase> {
+        let Ok(admin_url) = env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL is not set, skipping");
+            return None;
+        };
+        let name = format!("zuzu_test_{}", uuid::Uuid::new_v4().simple());
+        let mut admin = PgConnection::establish(&admin_url).expect("TEST_DATABASE_URL is reachable");
+        sql_query(format!("CREATE DATABASE {}", name)).execute(&mut admin).unwrap();
+
+        let (base, query) = admin_url.split_once('?').map_or((admin_url.as_str(), None), |(b, q)| (b, Some(q)));
+        let url = format!(
+            "{}/{}{}",
+            &base[..base.rfind('/').unwrap()],
+            name,
+            query.map(|q| format!("?{}", q)).unwrap_or_default()
+        );
+        Some(TestDatabase { admin_url, name, url })
+    }
+
+    /// A database with every migration applied.
+    pub(crate) fn migrated() -> Option<TestDatabase> {
+        let database = TestDatabase::create()?;
+        run_pending(&mut database.connect()).unwrap();
+        Some(database)
+    }
+
+    pub(crate) fn connect(&self) -> PgConnection {
+        PgConnection::establish(&self.url).unwrap()
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        if let Ok(mut admin) = PgConnection::establish(&self.admin_url) {
+            let _ = sql_query(format!("DROP DATABASE {} WITH (FORCE)", self.name)).execute(&mut admin);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use actix_web::{web, Error};
+use diesel::result::Error as DieselError;
+use diesel_async::scoped_futures::{ScopedBoxFuture, ScopedFutureExt};
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+
+use crate::config::database::DbPool;
+use crate::utils::errors::AppError;
+
+/// Why a unit of work was rolled back: the work gave up with an
+/// [`AppError`], or the database itself failed.
+#[derive(Debug)]
+enum Rollback {
+    App(AppError),
+    Database(DieselError),
+}
+
+impl From<DieselError> for Rollback {
+    fn from(e: DieselError) -> Self {
+        Rollback::Database(e)
+    }
+}
+
+impl From<Rollback> for AppError {
+    fn from(rollback: Rollback) -> Self {
+        match rollback {
+            Rollback::App(e) => e,
+            Rollback::Database(e) => AppError::from(e),
+        }
+    }
+}
+
+/// Groups database operations that span several tables so they commit
+/// together or not at all, on one pooled connection:
+///
+/// ```ignore
+/// UnitOfWork::new(pool).run(move |conn| async move {
+///     let user = UserRepository::insert_account(conn, &new_user).await?;
+///     UserRepository::insert_password_hash(conn, &hash_for(user.id)).await?;
+///     Ok(user)
+/// }.scope_boxed()).await
+/// ```
+pub struct UnitOfWork {
+    pool: web::Data<DbPool>,
+}
+
+impl UnitOfWork {
+    pub fn new(pool: web::Data<DbPool>) -> Self {
+        UnitOfWork { pool }
+    }
+
+    /// Runs `work` in a transaction; returning an error rolls back everything
+    /// it did.
+    pub async fn run<T, F>(&self, work: F) -> Result<T, Error>
+    where
+        F: for<'c> FnOnce(&'c mut AsyncPgConnection) -> ScopedBoxFuture<'static, 'c, Result<T, AppError>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let mut conn = self.pool.get().await.map_err(AppError::from)?;
+        let conn: &mut AsyncPgConnection = &mut conn;
+        let result = conn
+            .transaction(|conn| async move { work(conn).await.map_err(Rollback::App) }.scope_boxed())
+            .await;
+        Ok(result.map_err(AppError::from)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollback_reports_the_works_own_error() {
+        let error = AppError::from(Rollback::App(AppError::NotFound("Role not found".to_string())));
+        assert!(matches!(error, AppError::NotFound(message) if message == "Role not found"));
+
+        let error = AppError::from(Rollback::Database(DieselError::NotFound));
+        assert!(matches!(error, AppError::NotFound(_)));
+    }
+}
@@ -0,0 +1,50 @@
+use actix_web::web;
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use crate::infrastructure::external::otp_sender::{OtpMessage, OtpSender};
+use crate::utils::errors::AppError;
+
+/// Sends one-time passwords by email through an SMTP relay.
+pub struct EmailOtpSender {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl EmailOtpSender {
+    pub fn new(host: &str, port: u16, username: String, password: String, from: &str) -> Result<Self, AppError> {
+        let transport = SmtpTransport::starttls_relay(host)
+            .map_err(|e| AppError::InternalError(format!("Invalid SMTP relay: {}", e)))?
+            .port(port)
+            .credentials(Credentials::new(username, password))
+            .build();
+        let from = from
+            .parse::<Mailbox>()
+            .map_err(|e| AppError::InternalError(format!("Invalid sender address: {}", e)))?;
+        Ok(EmailOtpSender { transport, from })
+    }
+}
+
+#[async_trait]
+impl OtpSender for EmailOtpSender {
+    async fn send(&self, message: &OtpMessage) -> Result<(), AppError> {
+        let to = message
+            .recipient
+            .parse::<Mailbox>()
+            .map_err(|e| AppError::BadRequest(format!("Invalid email address: {}", e)))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject("Your verification code")
+            .body(message.body())
+            .map_err(|e| AppError::InternalError(e.to_string()))?;
+
+        let transport = self.transport.clone();
+        web::block(move || transport.send(&email))
+            .await
+            .map_err(|e| AppError::InternalError(e.to_string()))?
+            .map_err(|e| AppError::ServiceUnavailable(format!("Failed to send email: {}", e)))?;
+        Ok(())
+    }
+}
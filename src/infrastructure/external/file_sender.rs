@@ -0,0 +1,75 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use async_trait::async_trait;
+use chrono::Utc;
+use crate::domain::models::user::TwoFactorMethodEnum;
+use crate::infrastructure::external::otp_sender::{OtpMessage, OtpSender};
+use crate::utils::errors::AppError;
+
+/// Development and test sender: writes codes to an outbox file, or to the
+/// application log when no file is configured. Never use it in production.
+pub struct FileOtpSender {
+    method: TwoFactorMethodEnum,
+    path: Option<PathBuf>,
+}
+
+impl FileOtpSender {
+    pub fn new(method: TwoFactorMethodEnum, path: Option<PathBuf>) -> Self {
+        FileOtpSender { method, path }
+    }
+}
+
+#[async_trait]
+impl OtpSender for FileOtpSender {
+    async fn send(&self, message: &OtpMessage) -> Result<(), AppError> {
+        let line = format!(
+            "{} {:?} to={} purpose={} code={}",
+            Utc::now().to_rfc3339(),
+            self.method,
+            message.recipient,
+            message.purpose.as_str(),
+            message.code
+        );
+        match &self.path {
+            Some(path) => {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| AppError::InternalError(format!("Failed to open OTP outbox: {}", e)))?;
+                writeln!(file, "{}", line)
+                    .map_err(|e| AppError::InternalError(format!("Failed to write OTP outbox: {}", e)))?;
+            }
+            None => log::info!("OTP outbox: {}", line),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::otp::OtpPurpose;
+
+    #[actix_web::test]
+    async fn send_appends_code_to_outbox_file() {
+        let path = std::env::temp_dir().join(format!("otp_outbox_{}.log", uuid::Uuid::new_v4()));
+        let sender = FileOtpSender::new(TwoFactorMethodEnum::Sms, Some(path.clone()));
+        let message = OtpMessage {
+            recipient: "+6281234567890".to_string(),
+            code: "482913".to_string(),
+            purpose: OtpPurpose::Login,
+            expires_in_minutes: 5,
+        };
+
+        sender.send(&message).await.unwrap();
+        sender.send(&message).await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert!(contents.contains("to=+6281234567890"));
+        assert!(contents.contains("code=482913"));
+    }
+}
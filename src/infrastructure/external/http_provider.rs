@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use crate::utils::errors::AppError;
+
+/// A messaging provider reachable over HTTP (SMS gateways, WhatsApp Business APIs, ...).
+///
+/// Senders format the message; the adapter only knows how to hand it to the provider.
+#[async_trait]
+pub trait HttpProviderAdapter: Send + Sync {
+    async fn deliver(&self, to: &str, body: &str) -> Result<(), AppError>;
+}
+
+#[derive(Debug, Serialize)]
+struct ProviderRequest<'a> {
+    from: &'a str,
+    to: &'a str,
+    channel: &'a str,
+    body: &'a str,
+}
+
+/// Posts messages as JSON to a provider endpoint authenticated with a bearer token.
+pub struct JsonHttpProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_token: String,
+    sender_id: String,
+    channel: String,
+}
+
+impl JsonHttpProvider {
+    pub fn new(endpoint: String, api_token: String, sender_id: String, channel: impl Into<String>) -> Self {
+        JsonHttpProvider {
+            client: reqwest::Client::new(),
+            endpoint,
+            api_token,
+            sender_id,
+            channel: channel.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl HttpProviderAdapter for JsonHttpProvider {
+    async fn deliver(&self, to: &str, body: &str) -> Result<(), AppError> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .bearer_auth(&self.api_token)
+            .json(&ProviderRequest {
+                from: &self.sender_id,
+                to,
+                channel: &self.channel,
+                body,
+            })
+            .send()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("{} provider unreachable: {}", self.channel, e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ServiceUnavailable(format!(
+                "{} provider rejected message with status {}",
+                self.channel,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
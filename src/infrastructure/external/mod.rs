@@ -0,0 +1,6 @@
+pub mod otp_sender;
+pub mod email;
+pub mod http_provider;
+pub mod sms;
+pub mod whatsapp;
+pub mod file_sender;
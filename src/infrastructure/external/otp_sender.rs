@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::domain::models::otp::OtpPurpose;
+use crate::domain::models::user::TwoFactorMethodEnum;
+use crate::utils::errors::AppError;
+
+/// A one-time password ready to be delivered to its recipient.
+#[derive(Debug, Clone)]
+pub struct OtpMessage {
+    pub recipient: String,
+    pub code: String,
+    pub purpose: OtpPurpose,
+    pub expires_in_minutes: i64,
+}
+
+impl OtpMessage {
+    pub fn body(&self) -> String {
+        if self.purpose == OtpPurpose::MagicLink {
+            return format!(
+                "Use this link to sign in: {} It expires in {} minutes and works once, on the device that requested it.",
+                self.code, self.expires_in_minutes
+            );
+        }
+        format!(
+            "Your {} verification code is {}. It expires in {} minutes. Do not share it with anyone.",
+            self.purpose.label(),
+            self.code,
+            self.expires_in_minutes
+        )
+    }
+}
+
+/// Delivers one-time passwords over a single channel.
+#[async_trait]
+pub trait OtpSender: Send + Sync {
+    async fn send(&self, message: &OtpMessage) -> Result<(), AppError>;
+}
+
+/// The senders available to the application, keyed by the 2FA method they serve.
+#[derive(Clone, Default)]
+pub struct OtpSenders {
+    senders: HashMap<TwoFactorMethodEnum, Arc<dyn OtpSender>>,
+}
+
+impl OtpSenders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, method: TwoFactorMethodEnum, sender: Arc<dyn OtpSender>) -> Self {
+        self.senders.insert(method, sender);
+        self
+    }
+
+    pub fn get(&self, method: TwoFactorMethodEnum) -> Result<Arc<dyn OtpSender>, AppError> {
+        self.senders.get(&method).cloned().ok_or_else(|| {
+            AppError::ServiceUnavailable(format!("No delivery channel configured for {:?}", method))
+        })
+    }
+}
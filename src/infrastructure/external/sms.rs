@@ -0,0 +1,23 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::infrastructure::external::http_provider::HttpProviderAdapter;
+use crate::infrastructure::external::otp_sender::{OtpMessage, OtpSender};
+use crate::utils::errors::AppError;
+
+/// Sends one-time passwords as text messages through an HTTP SMS gateway.
+pub struct SmsOtpSender {
+    provider: Arc<dyn HttpProviderAdapter>,
+}
+
+impl SmsOtpSender {
+    pub fn new(provider: Arc<dyn HttpProviderAdapter>) -> Self {
+        SmsOtpSender { provider }
+    }
+}
+
+#[async_trait]
+impl OtpSender for SmsOtpSender {
+    async fn send(&self, message: &OtpMessage) -> Result<(), AppError> {
+        self.provider.deliver(&message.recipient, &message.body()).await
+    }
+}
@@ -0,0 +1,25 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::infrastructure::external::http_provider::HttpProviderAdapter;
+use crate::infrastructure::external::otp_sender::{OtpMessage, OtpSender};
+use crate::utils::errors::AppError;
+
+/// Sends one-time passwords as WhatsApp messages through an HTTP business API.
+pub struct WhatsappOtpSender {
+    provider: Arc<dyn HttpProviderAdapter>,
+}
+
+impl WhatsappOtpSender {
+    pub fn new(provider: Arc<dyn HttpProviderAdapter>) -> Self {
+        WhatsappOtpSender { provider }
+    }
+}
+
+#[async_trait]
+impl OtpSender for WhatsappOtpSender {
+    async fn send(&self, message: &OtpMessage) -> Result<(), AppError> {
+        // WhatsApp renders *text* in bold, which makes the code easy to spot.
+        let body = message.body().replace(&message.code, &format!("*{}*", message.code));
+        self.provider.deliver(&message.recipient, &body).await
+    }
+}
@@ -1 +1,2 @@
-pub mod database;
\ No newline at end of file
+pub mod database;
+pub mod external;
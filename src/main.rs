@@ -13,22 +13,35 @@ use api::routes;
 async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "debug");
     env_logger::init();
-    let pool = match config::database::init_pool() {
-        Ok(pool) => Some(pool),
-        Err(e) => {
-            eprintln!("Failed to create pool: {}", e);
-            None
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        if let Err(e) = infrastructure::database::migrations::command(&args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
-    };
+        return Ok(());
+    }
+    let pool = config::database::init_pool().await.map_err(std::io::Error::other)?;
+    let otp_senders = Data::new(config::otp::init_otp_senders());
+    let login_limiter = Data::new(config::security::init_login_rate_limiter());
+    let policy_engine = Data::new(config::policy::init_policy_engine());
+    if config::database::run_migrations_on_startup() {
+        infrastructure::database::migrations::migrate_on_startup(config::get_database_url())
+            .await
+            .map_err(std::io::Error::other)?;
+    }
+    config::database::spawn_pool_monitor(pool.clone());
+    domain::services::user_services::spawn_account_purge(Data::new(pool.clone()));
+    let pool = Data::new(pool);
     HttpServer::new(move || {
-        let mut app = App::new()
+        App::new()
+            .app_data(pool.clone())
+            .app_data(otp_senders.clone())
+            .app_data(login_limiter.clone())
+            .app_data(policy_engine.clone())
             .wrap(config::error_handling::init_error_handlers())
             .wrap(Logger::default())
-            .configure(routes::user_routes::init);
-        if pool.is_some() {
-            app = app.app_data(Data::new(pool.clone().unwrap()));
-        }
-        app
+            .configure(routes::user_routes::init)
     })
     .bind("127.0.0.1:8082")?
     .run()
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+struct Entries<K, V> {
+    values: HashMap<K, (Instant, V)>,
+    // Bumped on every invalidation so values computed before it are discarded.
+    generation: u64,
+}
+
+/// Small in-process cache whose entries expire after a fixed time to live.
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<Entries<K, V>>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    pub fn new(ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entries: Mutex::new(Entries { values: HashMap::new(), generation: 0 }),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .values
+            .get(key)
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Token to pass to [`TtlCache::insert`] for a value about to be computed.
+    pub fn generation(&self) -> u64 {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).generation
+    }
+
+    /// Stores `value` unless the cache was invalidated since `generation` was read.
+    pub fn insert(&self, key: K, value: V, generation: u64) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.generation != generation {
+            return;
+        }
+        let ttl = self.ttl;
+        entries.values.retain(|_, (stored, _)| stored.elapsed() < ttl);
+        entries.values.insert(key, (Instant::now(), value));
+    }
+
+    pub fn invalidate(&self, key: &K) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.values.remove(key);
+        entries.generation += 1;
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.values.clear();
+        entries.generation += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_values_until_they_expire() {
+        let cache = TtlCache::new(Duration::from_millis(20));
+        cache.insert("alice", 1, cache.generation());
+        assert_eq!(cache.get(&"alice"), Some(1));
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(cache.get(&"alice"), None);
+    }
+
+    #[test]
+    fn invalidate_and_clear_drop_entries() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert("alice", 1, cache.generation());
+        cache.insert("bob", 2, cache.generation());
+        cache.invalidate(&"alice");
+        assert_eq!(cache.get(&"alice"), None);
+        assert_eq!(cache.get(&"bob"), Some(2));
+        cache.clear();
+        assert_eq!(cache.get(&"bob"), None);
+    }
+
+    #[test]
+    fn values_computed_before_an_invalidation_are_not_stored() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        let generation = cache.generation();
+        cache.clear();
+        cache.insert("alice", 1, generation);
+        assert_eq!(cache.get(&"alice"), None);
+    }
+}
@@ -25,6 +25,9 @@ pub struct Claim {
     pub(crate) exp: DateTime<Utc>,
     pub(crate) iat: DateTime<Utc>,
     pub(crate) sub: String, // subject
+    pub(crate) roles: Option<Vec<String>>,       // role names, when embedded
+    pub(crate) permissions: Option<Vec<String>>, // compact grants like "users:rw", when embedded
+    pub(crate) amr: Option<Vec<String>>,         // how the subject signed in, on access tokens
 }
 
 pub struct Password {
@@ -55,6 +58,15 @@ impl Token for Claim {
         claims.not_before(&self.nbf.to_rfc3339()).unwrap();
         claims.issued_at(&self.iat.to_rfc3339()).unwrap();
         claims.token_identifier(&self.jti).unwrap();
+        if let Some(roles) = &self.roles {
+            claims.add_additional("roles", roles.clone()).unwrap();
+        }
+        if let Some(permissions) = &self.permissions {
+            claims.add_additional("permissions", permissions.clone()).unwrap();
+        }
+        if let Some(amr) = &self.amr {
+            claims.add_additional("amr", amr.clone()).unwrap();
+        }
 
         let key = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, SECRET_KEY.as_str())
             .expect("Failed to decode key");
@@ -71,56 +83,61 @@ impl Token for Claim {
     }
 
     fn load_claims(&self, token: &str) -> Result<Claims, AppError> {
-        let key = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, SECRET_KEY.as_str())
-            .expect("Failed to decode key");
+        decrypt_token(token)
+    }
+}
 
-        let sk = SymmetricKey::<V4>::from(&key).unwrap();
-        let pid = Id::from(&sk);
-        let mut footer = Footer::new();
-        footer.key_id(&pid);
-        let validation_rules = ClaimsValidationRules::new();
-        let untrusted_token = match UntrustedToken::<Local, V4>::try_from(token) {
-            Ok(token) => token,
-            Err(_) => return Err(AppError::Unauthorized("Invalid Token".to_string())),
-        };
-        let claims: Result<Claims, AppError> = match local::decrypt(
-            &sk,
-            &untrusted_token,
-            &validation_rules,
-            None,
-            Some(b"implisit Assertion"),
-        ) {
-            Ok(claims) => claims
-                .payload_claims()
-                .cloned()
-                .ok_or(AppError::Unauthorized("Unauthorized Access".to_string())),
-            Err(e) => {
-                if let ClaimValidation(e) = e {
-                    match e {
-                        ClaimValidationError::Nbf => {
-                            return Err(AppError::Unauthorized(
-                                "Failed to validate token time".to_string(),
-                            ))
-                        }
-                        ClaimValidationError::Exp => {
-                            return Err(AppError::Unauthorized("Token Expired".to_string()))
-                        }
-                        ClaimValidationError::Aud => {
-                            return Err(AppError::Unauthorized(
-                                "Failed to validate Audience ".to_string(),
-                            ))
-                        }
-                        _ => return Err(AppError::Unauthorized("Unauthorized Access".to_string())),
+/// Decrypts a token issued by [`Token::generate_token`] and validates its time claims.
+pub fn decrypt_token(token: &str) -> Result<Claims, AppError> {
+    let key = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, SECRET_KEY.as_str())
+        .expect("Failed to decode key");
+
+    let sk = SymmetricKey::<V4>::from(&key).unwrap();
+    let pid = Id::from(&sk);
+    let mut footer = Footer::new();
+    footer.key_id(&pid);
+    let validation_rules = ClaimsValidationRules::new();
+    let untrusted_token = match UntrustedToken::<Local, V4>::try_from(token) {
+        Ok(token) => token,
+        Err(_) => return Err(AppError::Unauthorized("Invalid Token".to_string())),
+    };
+    let claims: Result<Claims, AppError> = match local::decrypt(
+        &sk,
+        &untrusted_token,
+        &validation_rules,
+        None,
+        Some(b"implisit Assertion"),
+    ) {
+        Ok(claims) => claims
+            .payload_claims()
+            .cloned()
+            .ok_or(AppError::Unauthorized("Unauthorized Access".to_string())),
+        Err(e) => {
+            if let ClaimValidation(e) = e {
+                match e {
+                    ClaimValidationError::Nbf => {
+                        return Err(AppError::Unauthorized(
+                            "Failed to validate token time".to_string(),
+                        ))
+                    }
+                    ClaimValidationError::Exp => {
+                        return Err(AppError::Unauthorized("Token Expired".to_string()))
                     }
-                } else {
-                    Err(AppError::Unauthorized(
-                        "Error when claims token".to_string(),
-                    ))
+                    ClaimValidationError::Aud => {
+                        return Err(AppError::Unauthorized(
+                            "Failed to validate Audience ".to_string(),
+                        ))
+                    }
+                    _ => return Err(AppError::Unauthorized("Unauthorized Access".to_string())),
                 }
+            } else {
+                Err(AppError::Unauthorized(
+                    "Error when claims token".to_string(),
+                ))
             }
-        };
-        claims
-    }
+        }
+    };
+    claims
 }
 
 impl ArgonHash for Password {
@@ -162,6 +179,9 @@ mod tests {
             exp: Utc::now() + chrono::Duration::days(1),
             iat: Utc::now(),
             sub: "subject".to_string(),
+            roles: None,
+            permissions: None,
+            amr: None,
         };
         let token = claim.generate_token();
         assert_eq!(token.is_empty(), false);
@@ -178,6 +198,9 @@ mod tests {
             exp: Utc::now() - chrono::Duration::days(1),
             iat: Utc::now() - chrono::Duration::days(2),
             sub: "subject".to_string(),
+            roles: None,
+            permissions: None,
+            amr: None,
         };
         let token = claim.generate_token();
         let result = claim.load_claims(&token);
@@ -196,6 +219,9 @@ mod tests {
             exp: Utc::now() + chrono::Duration::days(1),
             iat: Utc::now(),
             sub: "subject".to_string(),
+            roles: None,
+            permissions: None,
+            amr: None,
         };
         let invalid_token = "invalid.token.string";
         let result = claim.load_claims(invalid_token);
@@ -214,6 +240,9 @@ mod tests {
             exp: Utc::now() + chrono::Duration::days(2),
             iat: Utc::now(),
             sub: "subject".to_string(),
+            roles: None,
+            permissions: None,
+            amr: None,
         };
         let token = claim.generate_token();
         let result = claim.load_claims(&token);
@@ -232,11 +261,36 @@ mod tests {
             exp: Utc::now() + chrono::Duration::days(1),
             iat: Utc::now(),
             sub: "subject".to_string(),
+            roles: None,
+            permissions: None,
+            amr: None,
         };
         let token = claim.generate_token();
         let result = claim.load_claims(&token);
         assert!(result.is_ok());
     }
+    #[test]
+    fn load_claims_returns_embedded_roles_and_permissions() {
+        env::set_var("TOKEN_SECRET_KEY", "mL7h0mMOsML8DRNXfqGcc57j+AWnzTws9jgujQxq0xs=");
+        let claim = Claim {
+            iss: "provider".to_string(),
+            jti: "token_id".to_string(),
+            aud: "audience".to_string(),
+            nbf: Utc::now(),
+            exp: Utc::now() + chrono::Duration::days(1),
+            iat: Utc::now(),
+            sub: "subject".to_string(),
+            roles: Some(vec!["editor".to_string()]),
+            permissions: Some(vec!["users:ru".to_string()]),
+            amr: Some(vec!["pwd".to_string()]),
+        };
+        let token = claim.generate_token();
+        let claims = claim.load_claims(&token).unwrap();
+        assert_eq!(claims.get_claim("roles"), Some(&serde_json::json!(["editor"])));
+        assert_eq!(claims.get_claim("permissions"), Some(&serde_json::json!(["users:ru"])));
+        assert_eq!(claims.get_claim("amr"), Some(&serde_json::json!(["pwd"])));
+    }
+
     #[test]
     fn hash_password_creates_valid_hash() {
         let pass = Password {
@@ -0,0 +1,92 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use crate::utils::errors::AppError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Cursors are handed to clients and come back in query strings. Signing them
+// lets us trust the position inside without a server-side lookup. Without a
+// key of their own they use one derived from the token key under a label, so
+// the token key itself only ever encrypts tokens.
+static CURSOR_SECRET_KEY: Lazy<Vec<u8>> = Lazy::new(|| match std::env::var("CURSOR_SECRET_KEY") {
+    Ok(key) => key.into_bytes(),
+    Err(_) => {
+        let token_key = std::env::var("TOKEN_SECRET_KEY").expect("CURSOR_SECRET_KEY or TOKEN_SECRET_KEY must be set");
+        let mut mac = HmacSha256::new_from_slice(token_key.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(b"zuzu cursor signing key");
+        mac.finalize().into_bytes().to_vec()
+    }
+});
+
+fn mac() -> HmacSha256 {
+    HmacSha256::new_from_slice(&CURSOR_SECRET_KEY).expect("HMAC accepts any key length")
+}
+
+/// Encodes `value` as an opaque `payload.signature` token, both base64url.
+pub fn sign<T: Serialize>(value: &T) -> String {
+    let payload = serde_json::to_vec(value).expect("cursor values serialize");
+    let mut mac = mac();
+    mac.update(&payload);
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(&payload),
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    )
+}
+
+/// Short digest of `value`, for cursors to remember what they were issued for.
+pub fn fingerprint<T: Serialize>(value: &T) -> u64 {
+    let digest = Sha256::digest(serde_json::to_vec(value).expect("cursor values serialize"));
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 digests are 32 bytes"))
+}
+
+/// Decodes a token made by [`sign`], rejecting anything altered or forged.
+pub fn verify<T: DeserializeOwned>(token: &str) -> Result<T, AppError> {
+    let invalid = || AppError::BadRequest("Invalid cursor".to_string());
+    let (payload, signature) = token.split_once('.').ok_or_else(invalid)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+    let mut mac = mac();
+    mac.update(&payload);
+    mac.verify_slice(&signature).map_err(|_| invalid())?;
+    serde_json::from_slice(&payload).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[derive(Debug, Serialize, serde::Deserialize, PartialEq)]
+    struct Position {
+        offset: i64,
+    }
+
+    #[test]
+    fn signed_values_round_trip() {
+        env::set_var("TOKEN_SECRET_KEY", "mL7h0mMOsML8DRNXfqGcc57j+AWnzTws9jgujQxq0xs=");
+        let token = sign(&Position { offset: 42 });
+        assert_eq!(verify::<Position>(&token).unwrap(), Position { offset: 42 });
+    }
+
+    #[test]
+    fn tampered_tokens_are_rejected() {
+        env::set_var("TOKEN_SECRET_KEY", "mL7h0mMOsML8DRNXfqGcc57j+AWnzTws9jgujQxq0xs=");
+        let token = sign(&Position { offset: 42 });
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode(br#"{"offset":0}"#), signature);
+        assert!(verify::<Position>(&forged).is_err());
+        assert!(verify::<Position>("not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn fingerprints_tell_values_apart() {
+        assert_eq!(fingerprint(&Position { offset: 1 }), fingerprint(&Position { offset: 1 }));
+        assert_ne!(fingerprint(&Position { offset: 1 }), fingerprint(&Position { offset: 2 }));
+    }
+}
@@ -2,6 +2,8 @@ use actix_web::{HttpResponse, ResponseError};
 use actix_web::dev::ServiceResponse;
 use actix_web::http::StatusCode;
 use actix_web::middleware::{ErrorHandlerResponse};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel_async::pooled_connection::deadpool::PoolError;
 use thiserror::Error;
 
 #[derive(Debug, Error, Clone)]
@@ -16,8 +18,122 @@ pub enum AppError{
     Unauthorized(String),
     #[error("Forbidden: {0}")]
     Forbidden(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Precondition Failed: {0}")]
+    PreconditionFailed(String),
+    #[error("Precondition Required: {0}")]
+    PreconditionRequired(String),
+    #[error("Too Many Requests: {0}")]
+    TooManyRequests(String),
     #[error("Internal Error: {0}")]
     InternalError(String),
     #[error("Service Unavailable: {0}")]
     ServiceUnavailable(String),
-}
\ No newline at end of file
+}
+
+/// Columns named in a Postgres constraint message such as
+/// `Key (lower(username::text))=(alice) already exists.`, without the
+/// functions and casts around them and without the offending values.
+fn constraint_columns(details: &str) -> Option<String> {
+    let start = details.find("Key (")? + "Key (".len();
+    let end = start + details[start..].find(")=(")?;
+    let key = &details[start..end];
+    let mut columns: Vec<&str> = Vec::new();
+    let mut rest = key;
+    while let Some(at) = rest.find(|c: char| c.is_alphanumeric() || c == '_') {
+        let tail = &rest[at..];
+        let len = tail.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(tail.len());
+        let word = &tail[..len];
+        let is_function = tail[len..].starts_with('(');
+        let is_cast = rest[..at].ends_with("::");
+        if !is_function && !is_cast && !columns.contains(&word) {
+            columns.push(word);
+        }
+        rest = &tail[len..];
+    }
+    (!columns.is_empty()).then(|| columns.join(", "))
+}
+
+/// Classifies database failures for clients. The original error, which may
+/// contain SQL or row values, only goes to the log.
+impl From<DieselError> for AppError {
+    fn from(e: DieselError) -> Self {
+        match e {
+            DieselError::NotFound => AppError::NotFound("Record not found".to_string()),
+            DieselError::DatabaseError(kind, info) => {
+                log::warn!("database error: {:?}: {}", kind, info.message());
+                let columns = info.details().and_then(constraint_columns);
+                match kind {
+                    DatabaseErrorKind::UniqueViolation => AppError::Conflict(match columns {
+                        Some(columns) => format!("{} is already taken", columns),
+                        None => "The record already exists".to_string(),
+                    }),
+                    DatabaseErrorKind::ForeignKeyViolation
+                        if info.details().is_some_and(|d| d.contains("still referenced")) =>
+                    {
+                        AppError::Conflict("The record is still referenced by other records".to_string())
+                    }
+                    DatabaseErrorKind::ForeignKeyViolation => AppError::ValidationError(match columns {
+                        Some(columns) => format!("{} refers to a record that does not exist", columns),
+                        None => "The record refers to a record that does not exist".to_string(),
+                    }),
+                    DatabaseErrorKind::NotNullViolation => AppError::ValidationError(match info.column_name() {
+                        Some(column) => format!("{} is required", column),
+                        None => "A required value is missing".to_string(),
+                    }),
+                    DatabaseErrorKind::CheckViolation => {
+                        AppError::ValidationError("A value is outside of its allowed range".to_string())
+                    }
+                    DatabaseErrorKind::SerializationFailure => AppError::Conflict(
+                        "The record was changed concurrently, try again".to_string(),
+                    ),
+                    DatabaseErrorKind::ClosedConnection => {
+                        AppError::ServiceUnavailable("Database unavailable".to_string())
+                    }
+                    _ => AppError::InternalError("Database error".to_string()),
+                }
+            }
+            DieselError::BrokenTransactionManager => {
+                log::error!("database error: {}", e);
+                AppError::ServiceUnavailable("Database unavailable".to_string())
+            }
+            e => {
+                log::error!("database error: {}", e);
+                AppError::InternalError("Database error".to_string())
+            }
+        }
+    }
+}
+
+impl From<PoolError> for AppError {
+    fn from(e: PoolError) -> Self {
+        log::error!("database pool error: {}", e);
+        AppError::ServiceUnavailable("Database unavailable".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constraint_columns_skip_functions_casts_and_values() {
+        assert_eq!(
+            constraint_columns("Key (lower(username::text))=(alice) already exists.").as_deref(),
+            Some("username")
+        );
+        assert_eq!(
+            constraint_columns("Key (account_id, role_id)=(1, 2) already exists.").as_deref(),
+            Some("account_id, role_id")
+        );
+        assert_eq!(constraint_columns("Failing row contains (x)."), None);
+    }
+
+    #[test]
+    fn unknown_database_errors_do_not_leak_details() {
+        let error = AppError::from(DieselError::QueryBuilderError("SELECT secret FROM accounts".into()));
+        assert!(matches!(error, AppError::InternalError(message) if message == "Database error"));
+        assert!(matches!(AppError::from(DieselError::NotFound), AppError::NotFound(_)));
+    }
+}
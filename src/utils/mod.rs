@@ -1,4 +1,10 @@
 pub mod context;
 pub(crate) mod errors;
+pub mod cache;
 pub mod crypto;
+pub mod cursor;
+pub mod otp;
+pub mod phone;
+pub mod rate_limit;
+pub mod webauthn;
 mod password_hashing;
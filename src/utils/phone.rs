@@ -0,0 +1,53 @@
+use once_cell::sync::Lazy;
+use phonenumber::country;
+use phonenumber::Mode;
+use crate::utils::errors::AppError;
+
+// Region used to interpret numbers written without a leading `+country code`.
+static DEFAULT_PHONE_REGION: Lazy<Option<country::Id>> = Lazy::new(|| {
+    std::env::var("DEFAULT_PHONE_REGION")
+        .ok()
+        .and_then(|region| region.trim().to_uppercase().parse().ok())
+});
+
+/// Parses a phone number and returns it in E.164 form (`+6281234567890`).
+pub fn normalize_phone_number(raw: &str) -> Result<String, AppError> {
+    normalize_phone_number_in(raw, *DEFAULT_PHONE_REGION)
+}
+
+pub fn normalize_phone_number_in(raw: &str, region: Option<country::Id>) -> Result<String, AppError> {
+    let invalid = || AppError::ValidationError(format!("Invalid phone number: {}", raw));
+    let number = phonenumber::parse(region, raw.trim()).map_err(|_| invalid())?;
+    if !phonenumber::is_valid(&number) {
+        return Err(invalid());
+    }
+    Ok(number.format().mode(Mode::E164).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_international_number_with_formatting() {
+        let normalized = normalize_phone_number_in("+62 812-3456-7890", None).unwrap();
+        assert_eq!(normalized, "+6281234567890");
+    }
+
+    #[test]
+    fn normalizes_national_number_with_region() {
+        let normalized = normalize_phone_number_in("0812 3456 7890", Some(country::Id::ID)).unwrap();
+        assert_eq!(normalized, "+6281234567890");
+    }
+
+    #[test]
+    fn rejects_national_number_without_region() {
+        assert!(normalize_phone_number_in("081234567890", None).is_err());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(normalize_phone_number_in("not a number", None).is_err());
+        assert!(normalize_phone_number_in("+1 555", None).is_err());
+    }
+}
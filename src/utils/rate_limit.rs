@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::utils::errors::AppError;
+
+// Above this many tracked keys, expired windows are swept on the next check.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Fixed-window, in-process request limiter keyed by arbitrary strings
+/// (client address, login identifier, ...).
+pub struct RateLimiter {
+    max_requests: u32,
+    window: Duration,
+    hits: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        RateLimiter {
+            max_requests,
+            window,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a request for `key`, failing once the window's budget is spent.
+    pub fn check(&self, key: &str) -> Result<(), AppError> {
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        if hits.len() > SWEEP_THRESHOLD {
+            hits.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        }
+
+        let entry = hits.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(entry.0) >= self.window {
+            *entry = (now, 0);
+        }
+        if entry.1 >= self.max_requests {
+            let retry_in = self.window.saturating_sub(now.duration_since(entry.0));
+            return Err(AppError::TooManyRequests(format!(
+                "Too many attempts, retry in {} seconds",
+                retry_in.as_secs().max(1)
+            )));
+        }
+        entry.1 += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_requests_up_to_the_limit() {
+        let limiter = RateLimiter::new(3, Duration::from_secs(60));
+        assert!(limiter.check("ip:127.0.0.1").is_ok());
+        assert!(limiter.check("ip:127.0.0.1").is_ok());
+        assert!(limiter.check("ip:127.0.0.1").is_ok());
+        assert!(limiter.check("ip:127.0.0.1").is_err());
+    }
+
+    #[test]
+    fn keys_are_limited_independently() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        assert!(limiter.check("login:alice").is_ok());
+        assert!(limiter.check("login:bob").is_ok());
+        assert!(limiter.check("login:alice").is_err());
+    }
+
+    #[test]
+    fn window_resets_after_it_elapses() {
+        let limiter = RateLimiter::new(1, Duration::from_millis(20));
+        assert!(limiter.check("ip:127.0.0.1").is_ok());
+        assert!(limiter.check("ip:127.0.0.1").is_err());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(limiter.check("ip:127.0.0.1").is_ok());
+    }
+}
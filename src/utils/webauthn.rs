@@ -0,0 +1,357 @@
+use std::io::Cursor;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ciborium::Value;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use crate::utils::errors::AppError;
+
+// Authenticator data flags (WebAuthn §6.1).
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+// COSE identifiers for an ES256 (ECDSA P-256 with SHA-256) EC2 key.
+pub const COSE_ALG_ES256: i64 = -7;
+const COSE_KTY_EC2: i64 = 2;
+const COSE_CRV_P256: i64 = 1;
+
+pub fn encode(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub fn decode(value: &str) -> Result<Vec<u8>, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| AppError::BadRequest("Invalid base64url value".to_string()))
+}
+
+pub fn rp_id_hash(rp_id: &str) -> [u8; 32] {
+    Sha256::digest(rp_id.as_bytes()).into()
+}
+
+#[derive(Debug, Deserialize)]
+struct CollectedClientData {
+    #[serde(rename = "type")]
+    ceremony_type: String,
+    challenge: String,
+    origin: String,
+}
+
+/// Checks the browser-collected client data against what the server issued.
+pub fn verify_client_data(
+    client_data_json: &[u8],
+    expected_type: &str,
+    expected_challenge: &[u8],
+    expected_origin: &str,
+) -> Result<(), AppError> {
+    let client_data: CollectedClientData = serde_json::from_slice(client_data_json)
+        .map_err(|_| AppError::BadRequest("Malformed client data".to_string()))?;
+    if client_data.ceremony_type != expected_type {
+        return Err(AppError::Unauthorized("Unexpected ceremony type".to_string()));
+    }
+    if decode(&client_data.challenge)? != expected_challenge {
+        return Err(AppError::Unauthorized("Challenge mismatch".to_string()));
+    }
+    if client_data.origin != expected_origin {
+        return Err(AppError::Unauthorized("Origin mismatch".to_string()));
+    }
+    Ok(())
+}
+
+/// A credential created during registration.
+#[derive(Debug, Clone)]
+pub struct AttestedCredential {
+    pub credential_id: Vec<u8>,
+    /// SEC1 uncompressed P-256 point.
+    pub public_key: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthenticatorData {
+    pub rp_id_hash: [u8; 32],
+    pub flags: u8,
+    pub sign_count: u32,
+    pub attested_credential: Option<AttestedCredential>,
+}
+
+impl AuthenticatorData {
+    pub fn parse(bytes: &[u8]) -> Result<Self, AppError> {
+        let malformed = || AppError::BadRequest("Malformed authenticator data".to_string());
+        if bytes.len() < 37 {
+            return Err(malformed());
+        }
+        let rp_id_hash: [u8; 32] = bytes[..32].try_into().map_err(|_| malformed())?;
+        let flags = bytes[32];
+        let sign_count = u32::from_be_bytes(bytes[33..37].try_into().map_err(|_| malformed())?);
+
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+            // aaguid (16) || credential id length (2) || credential id || COSE key
+            let rest = &bytes[37..];
+            if rest.len() < 18 {
+                return Err(malformed());
+            }
+            let id_len = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+            let id_end = 18 + id_len;
+            if rest.len() < id_end {
+                return Err(malformed());
+            }
+            let credential_id = rest[18..id_end].to_vec();
+            let mut cursor = Cursor::new(&rest[id_end..]);
+            let cose_key: Value = ciborium::de::from_reader(&mut cursor).map_err(|_| malformed())?;
+            Some(AttestedCredential {
+                credential_id,
+                public_key: cose_key_to_sec1(&cose_key)?,
+            })
+        } else {
+            None
+        };
+
+        Ok(AuthenticatorData {
+            rp_id_hash,
+            flags,
+            sign_count,
+            attested_credential,
+        })
+    }
+
+    pub fn user_present(&self) -> bool {
+        self.flags & FLAG_USER_PRESENT != 0
+    }
+
+    pub fn user_verified(&self) -> bool {
+        self.flags & FLAG_USER_VERIFIED != 0
+    }
+
+    /// Rejects data that was not produced for this relying party or without
+    /// the user touching the authenticator.
+    pub fn verify_for(&self, rp_id: &str) -> Result<(), AppError> {
+        if self.rp_id_hash != rp_id_hash(rp_id) {
+            return Err(AppError::Unauthorized("Relying party mismatch".to_string()));
+        }
+        if !self.user_present() {
+            return Err(AppError::Unauthorized("User presence is required".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn map_get<'a>(map: &'a [(Value, Value)], key: &Value) -> Option<&'a Value> {
+    map.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+fn cose_key_to_sec1(key: &Value) -> Result<Vec<u8>, AppError> {
+    let unsupported = || AppError::BadRequest("Only ES256 (P-256) credentials are supported".to_string());
+    let map = key.as_map().ok_or_else(unsupported)?;
+    let int = |label: i64| map_get(map, &Value::from(label)).and_then(|v| v.as_integer()).map(i128::from);
+    let bytes = |label: i64| map_get(map, &Value::from(label)).and_then(|v| v.as_bytes());
+
+    if int(1) != Some(COSE_KTY_EC2 as i128)
+        || int(3) != Some(COSE_ALG_ES256 as i128)
+        || int(-1) != Some(COSE_CRV_P256 as i128)
+    {
+        return Err(unsupported());
+    }
+    let (x, y) = match (bytes(-2), bytes(-3)) {
+        (Some(x), Some(y)) if x.len() == 32 && y.len() == 32 => (x, y),
+        _ => return Err(unsupported()),
+    };
+    let mut sec1 = Vec::with_capacity(65);
+    sec1.push(0x04);
+    sec1.extend_from_slice(x);
+    sec1.extend_from_slice(y);
+    VerifyingKey::from_sec1_bytes(&sec1).map_err(|_| unsupported())?;
+    Ok(sec1)
+}
+
+/// Extracts the authenticator data from a `none` attestation object.
+///
+/// Attestation statements are not evaluated: registration asks for
+/// `attestation: "none"`, so browsers strip them anyway.
+pub fn parse_attestation_object(bytes: &[u8]) -> Result<AuthenticatorData, AppError> {
+    let malformed = || AppError::BadRequest("Malformed attestation object".to_string());
+    let value: Value = ciborium::de::from_reader(bytes).map_err(|_| malformed())?;
+    let map = value.as_map().ok_or_else(malformed)?;
+    let fmt = map_get(map, &Value::from("fmt")).and_then(|v| v.as_text()).ok_or_else(malformed)?;
+    if fmt != "none" {
+        return Err(AppError::BadRequest(format!("Unsupported attestation format: {}", fmt)));
+    }
+    let auth_data = map_get(map, &Value::from("authData")).and_then(|v| v.as_bytes()).ok_or_else(malformed)?;
+    AuthenticatorData::parse(auth_data)
+}
+
+/// Verifies an assertion signature over `authenticatorData || SHA-256(clientDataJSON)`.
+pub fn verify_assertion_signature(
+    public_key: &[u8],
+    authenticator_data: &[u8],
+    client_data_json: &[u8],
+    signature: &[u8],
+) -> Result<(), AppError> {
+    let invalid = || AppError::Unauthorized("Invalid assertion signature".to_string());
+    let key = VerifyingKey::from_sec1_bytes(public_key).map_err(|_| invalid())?;
+    let signature = Signature::from_der(signature).map_err(|_| invalid())?;
+    let mut signed = authenticator_data.to_vec();
+    signed.extend_from_slice(&Sha256::digest(client_data_json));
+    key.verify(&signed, &signature).map_err(|_| invalid())
+}
+
+/// Detects cloned authenticators: once a counter is in use it must always grow.
+pub fn check_sign_count(stored: u32, received: u32) -> Result<(), AppError> {
+    if (stored != 0 || received != 0) && received <= stored {
+        return Err(AppError::Unauthorized("Authenticator sign counter did not increase".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::SigningKey;
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+
+    pub const RP_ID: &str = "localhost";
+    pub const ORIGIN: &str = "http://localhost:8082";
+
+    /// A minimal ES256 authenticator living in memory.
+    pub struct SoftwareAuthenticator {
+        pub credential_id: Vec<u8>,
+        key: SigningKey,
+        counter: u32,
+    }
+
+    impl SoftwareAuthenticator {
+        pub fn new() -> Self {
+            let mut credential_id = vec![0u8; 16];
+            OsRng.fill_bytes(&mut credential_id);
+            SoftwareAuthenticator {
+                credential_id,
+                key: SigningKey::random(&mut OsRng),
+                counter: 0,
+            }
+        }
+
+        pub fn client_data(ceremony: &str, challenge: &[u8], origin: &str) -> Vec<u8> {
+            serde_json::json!({
+                "type": ceremony,
+                "challenge": encode(challenge),
+                "origin": origin,
+            })
+            .to_string()
+            .into_bytes()
+        }
+
+        fn cose_key(&self) -> Vec<u8> {
+            let point = self.key.verifying_key().to_encoded_point(false);
+            let key = Value::Map(vec![
+                (Value::from(1), Value::from(COSE_KTY_EC2)),
+                (Value::from(3), Value::from(COSE_ALG_ES256)),
+                (Value::from(-1), Value::from(COSE_CRV_P256)),
+                (Value::from(-2), Value::Bytes(point.x().unwrap().to_vec())),
+                (Value::from(-3), Value::Bytes(point.y().unwrap().to_vec())),
+            ]);
+            let mut out = Vec::new();
+            ciborium::ser::into_writer(&key, &mut out).unwrap();
+            out
+        }
+
+        /// Returns an attestation object for `navigator.credentials.create()`.
+        pub fn attestation_object(&self, rp_id: &str) -> Vec<u8> {
+            let mut auth_data = rp_id_hash(rp_id).to_vec();
+            auth_data.push(FLAG_USER_PRESENT | FLAG_USER_VERIFIED | FLAG_ATTESTED_CREDENTIAL);
+            auth_data.extend_from_slice(&self.counter.to_be_bytes());
+            auth_data.extend_from_slice(&[0u8; 16]);
+            auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            auth_data.extend_from_slice(&self.credential_id);
+            auth_data.extend_from_slice(&self.cose_key());
+            let object = Value::Map(vec![
+                (Value::from("fmt"), Value::from("none")),
+                (Value::from("attStmt"), Value::Map(vec![])),
+                (Value::from("authData"), Value::Bytes(auth_data)),
+            ]);
+            let mut out = Vec::new();
+            ciborium::ser::into_writer(&object, &mut out).unwrap();
+            out
+        }
+
+        /// Returns `(authenticatorData, signature)` for `navigator.credentials.get()`.
+        pub fn assert(&mut self, rp_id: &str, client_data_json: &[u8]) -> (Vec<u8>, Vec<u8>) {
+            self.counter += 1;
+            let mut auth_data = rp_id_hash(rp_id).to_vec();
+            auth_data.push(FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+            auth_data.extend_from_slice(&self.counter.to_be_bytes());
+            let mut signed = auth_data.clone();
+            signed.extend_from_slice(&Sha256::digest(client_data_json));
+            let signature: Signature = self.key.sign(&signed);
+            (auth_data, signature.to_der().as_bytes().to_vec())
+        }
+    }
+
+    #[test]
+    fn registration_extracts_credential() {
+        let authenticator = SoftwareAuthenticator::new();
+        let auth_data = parse_attestation_object(&authenticator.attestation_object(RP_ID)).unwrap();
+        auth_data.verify_for(RP_ID).unwrap();
+        let credential = auth_data.attested_credential.unwrap();
+        assert_eq!(credential.credential_id, authenticator.credential_id);
+        assert_eq!(credential.public_key.len(), 65);
+    }
+
+    #[test]
+    fn registration_for_other_rp_is_rejected() {
+        let authenticator = SoftwareAuthenticator::new();
+        let auth_data = parse_attestation_object(&authenticator.attestation_object("evil.example")).unwrap();
+        assert!(auth_data.verify_for(RP_ID).is_err());
+    }
+
+    #[test]
+    fn client_data_checks_challenge_and_origin() {
+        let challenge = b"server-challenge";
+        let client_data = SoftwareAuthenticator::client_data("webauthn.create", challenge, ORIGIN);
+        assert!(verify_client_data(&client_data, "webauthn.create", challenge, ORIGIN).is_ok());
+        assert!(verify_client_data(&client_data, "webauthn.get", challenge, ORIGIN).is_err());
+        assert!(verify_client_data(&client_data, "webauthn.create", b"other", ORIGIN).is_err());
+        assert!(verify_client_data(&client_data, "webauthn.create", challenge, "https://evil.example").is_err());
+    }
+
+    #[test]
+    fn assertion_signature_verifies_with_registered_key() {
+        let mut authenticator = SoftwareAuthenticator::new();
+        let credential = parse_attestation_object(&authenticator.attestation_object(RP_ID))
+            .unwrap()
+            .attested_credential
+            .unwrap();
+        let client_data = SoftwareAuthenticator::client_data("webauthn.get", b"challenge", ORIGIN);
+        let (auth_data, signature) = authenticator.assert(RP_ID, &client_data);
+
+        assert!(verify_assertion_signature(&credential.public_key, &auth_data, &client_data, &signature).is_ok());
+        let parsed = AuthenticatorData::parse(&auth_data).unwrap();
+        assert_eq!(parsed.sign_count, 1);
+        assert!(parsed.user_verified());
+    }
+
+    #[test]
+    fn assertion_signature_rejects_tampered_client_data() {
+        let mut authenticator = SoftwareAuthenticator::new();
+        let credential = parse_attestation_object(&authenticator.attestation_object(RP_ID))
+            .unwrap()
+            .attested_credential
+            .unwrap();
+        let client_data = SoftwareAuthenticator::client_data("webauthn.get", b"challenge", ORIGIN);
+        let (auth_data, signature) = authenticator.assert(RP_ID, &client_data);
+        let forged = SoftwareAuthenticator::client_data("webauthn.get", b"other", ORIGIN);
+
+        assert!(verify_assertion_signature(&credential.public_key, &auth_data, &forged, &signature).is_err());
+    }
+
+    #[test]
+    fn sign_count_must_increase() {
+        assert!(check_sign_count(0, 0).is_ok());
+        assert!(check_sign_count(4, 5).is_ok());
+        assert!(check_sign_count(5, 5).is_err());
+        assert!(check_sign_count(5, 0).is_err());
+    }
+}